@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use quote::ToTokens;
-use syn::{parse_quote, Block, Expr, ItemFn};
+use syn::{parse_quote, Block, Expr, ItemFn, ReturnType, Type};
 
 #[proc_macro_attribute]
 pub fn token_context(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -16,14 +16,20 @@ pub fn token_context(attr: TokenStream, item: TokenStream) -> TokenStream {
     //     panic!("not ident");
     // };
     let block = &item_fn.block;
+    let ret: Type = match &item_fn.sig.output {
+        ReturnType::Default => parse_quote!(()),
+        ReturnType::Type(_, ty) => ty.as_ref().clone(),
+    };
     let new_block: Block = parse_quote!({
         // TODO use std::any::type_name::<#function name>();
         self.push_context(#attrs);
-        let res = #block;
+        // wrapped in a closure so that early returns (and ?) still pop the context
+        #[allow(clippy::redundant_closure_call)]
+        let res = (|| -> #ret #block)();
         self.pop_context();
         // println!("res: {:#?}", res);
         res
     });
-    item_fn.block = Box::new(new_block);
+    *item_fn.block = new_block;
     proc_macro::TokenStream::from(item_fn.to_token_stream())
 }
@@ -8,6 +8,15 @@ pub enum FieldOrComment {
     Comment(Comment),
 }
 
+impl FieldOrComment {
+    pub fn span(&self) -> Span {
+        match self {
+            FieldOrComment::Field(field) => field.span,
+            FieldOrComment::Comment(comment) => comment.0,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Identifier(pub Span);
 
@@ -15,13 +24,34 @@ pub struct Identifier(pub Span);
 pub struct Field {
     pub key: Identifier,
     pub value: Expression,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Handler {
     pub key: Identifier,
+    pub receiver: Receiver,
     pub value: Identifier,
+    pub span: Span,
+}
+
+/// What a name is looked up on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Receiver {
+    /// Prefixed with self. or this. (the span of which is stored), refers to the component
+    SelfRef(Span),
+    /// No prefix, such as a loop variable
+    Implicit,
 }
 
 #[derive(Debug)]
 pub struct Comment(pub Span);
+
+impl Comment {
+    /// Text of a doc comment, without the leading "///" and the single space after it
+    pub fn doc_text<'a>(&self, source: &'a str) -> &'a str {
+        let text = &source[self.0.as_range()];
+        let text = text.strip_prefix("///").unwrap_or(text);
+        text.strip_prefix(' ').unwrap_or(text).trim_end()
+    }
+}
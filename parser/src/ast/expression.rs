@@ -1,23 +1,79 @@
 use crate::lexer::Span;
 
-use super::common::{FieldOrComment, Identifier};
+use super::common::{FieldOrComment, Identifier, Receiver};
 
 #[derive(Debug)]
 pub enum Expression {
-    Value(Value),
+    Value {
+        value: Value,
+        span: Span,
+    },
     Unary {
         op: UnaryOp,
         val: Box<Expression>,
+        span: Span,
     },
     WithUnit {
         val: Box<Expression>,
         unit: Unit,
+        span: Span,
     },
     Binary {
         left: Box<Expression>,
         op: BinaryOp,
         right: Box<Expression>,
+        span: Span,
+    },
+    /// if c {a} else {b}, or c ? a : b
+    Conditional {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Span,
+    },
+    /// expr.field
+    FieldAccess {
+        val: Box<Expression>,
+        field: Identifier,
+        span: Span,
+    },
+    /// expr.method(args)
+    MethodCall {
+        val: Box<Expression>,
+        method: Identifier,
+        arguments: Vec<Expression>,
+        span: Span,
     },
+    /// expr[index]
+    Index {
+        val: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
+    },
+    /// |a, b| expr, or || expr without parameters
+    Closure {
+        params: Vec<Identifier>,
+        body: Box<Expression>,
+        span: Span,
+    },
+    Error(Span),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Value { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::WithUnit { span, .. }
+            | Expression::Binary { span, .. }
+            | Expression::Conditional { span, .. }
+            | Expression::FieldAccess { span, .. }
+            | Expression::MethodCall { span, .. }
+            | Expression::Index { span, .. }
+            | Expression::Closure { span, .. }
+            | Expression::Error(span) => *span,
+        }
+    }
 }
 
 // A literal contains a very constrained subset of all possible expressions,
@@ -26,25 +82,139 @@ pub enum Expression {
 pub struct Literal {
     pub value: Value,
     pub unit: Option<Unit>,
+    pub value_span: Span,
+    /// Value including the unit, if any
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub enum Value {
-    Variable(Vec<Identifier>),
+    Variable(Variable),
     EnumVariant(EnumVariant),
-    Float(Span),
-    Int(Span),
-    String(Span),
+    Float(FloatLiteral),
+    Int(IntLiteral),
+    Bool(BoolLiteral),
+    /// none or null
+    None(Span),
+    Color(ColorLiteral),
+    String(StringLiteral),
+    InterpolatedString(InterpolatedString),
     Object(Object),
     FunctionCall(FunctionCall),
     List(Vec<Expression>),
     Tuple(Vec<Expression>),
 }
 
+#[derive(Debug)]
+pub struct StringLiteral {
+    /// Span of the content between the quotes
+    pub content: Span,
+    /// The content with escape sequences (and {{ }}) decoded, raw strings are kept as is
+    pub value: String,
+    pub raw: bool,
+    /// Span including the quotes
+    pub span: Span,
+}
+
+impl Value {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(int) => Some(int.value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(bool) => Some(bool.value),
+            _ => None,
+        }
+    }
+
+    /// The value of a float, or of an int converted to a float
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(int) => Some(int.value as f64),
+            Value::Float(float) => Some(float.value),
+            _ => None,
+        }
+    }
+}
+
+/// A variable such as self.items.len or i
+#[derive(Debug)]
+pub struct Variable {
+    pub receiver: Receiver,
+    pub path: Vec<Identifier>,
+    pub span: Span,
+}
+
+/// An integer such as 1_000, 0xFF or 0b1010. Out of range
+/// values are reported as errors, and saturate to i64::MAX.
+/// -9223372036854775808 is a single literal with the value i64::MIN
+#[derive(Debug)]
+pub struct IntLiteral {
+    pub value: i64,
+    pub span: Span,
+}
+
+/// true or false
+#[derive(Debug)]
+pub struct BoolLiteral {
+    pub value: bool,
+    pub span: Span,
+}
+
+/// A float such as 1.5, .5 or 1e-3
+#[derive(Debug)]
+pub struct FloatLiteral {
+    pub value: f64,
+    pub span: Span,
+}
+
+/// A hex color such as #f04, #ff0044 or #ff004480
+#[derive(Debug)]
+pub struct ColorLiteral {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 255 if not specified
+    pub a: u8,
+    pub span: Span,
+}
+
+/// A string with embedded expressions, such as "Score: {self.score}"
+#[derive(Debug)]
+pub struct InterpolatedString {
+    pub segments: Vec<StringSegment>,
+    /// Span including the quotes
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum StringSegment {
+    /// Text between expressions, with escape sequences (and {{ }}) decoded
+    Literal {
+        value: String,
+        span: Span,
+    },
+    Expression(Expression),
+}
+
+impl StringSegment {
+    pub fn span(&self) -> Span {
+        match self {
+            StringSegment::Literal { span, .. } => *span,
+            StringSegment::Expression(expression) => expression.span(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Object {
     pub name: Option<Identifier>,
     pub fields: Vec<FieldOrComment>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -52,18 +222,27 @@ pub struct EnumVariant {
     pub name: Identifier,
     pub variant: Identifier,
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct FunctionCall {
+    pub receiver: Receiver,
     pub name: Identifier,
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Op {
     Binary(BinaryOp),
     Postfix(Unit),
+    /// c ? a : b
+    Conditional,
+    /// .field or .method(args)
+    Member,
+    /// [index]
+    Index,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -72,12 +251,50 @@ pub enum UnaryOp {
     Not,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Unit {
     Degrees,
     Radians,
+    Turns,
     Percent,
     Pixels,
+    Em,
+    Rem,
+    ViewportWidth,
+    ViewportHeight,
+    Seconds,
+    Milliseconds,
+}
+
+impl Unit {
+    /// How each unit is written after a number or expression, such as 5px.
+    /// Adding a unit here (and to the enum) is enough for it to be lexed and parsed.
+    pub const SUFFIXES: [(&'static str, Unit); 11] = [
+        ("deg", Unit::Degrees),
+        ("rad", Unit::Radians),
+        ("turn", Unit::Turns),
+        ("%", Unit::Percent),
+        ("px", Unit::Pixels),
+        ("em", Unit::Em),
+        ("rem", Unit::Rem),
+        ("vw", Unit::ViewportWidth),
+        ("vh", Unit::ViewportHeight),
+        ("s", Unit::Seconds),
+        ("ms", Unit::Milliseconds),
+    ];
+
+    pub fn from_suffix(suffix: &str) -> Option<Unit> {
+        Self::SUFFIXES
+            .into_iter()
+            .find_map(|(s, unit)| (s == suffix).then_some(unit))
+    }
+
+    pub fn suffix(self) -> &'static str {
+        Self::SUFFIXES
+            .into_iter()
+            .find_map(|(s, unit)| (unit == self).then_some(s))
+            .expect("every unit has a suffix")
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
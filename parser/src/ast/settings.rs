@@ -1,3 +1,5 @@
+use crate::lexer::Span;
+
 use super::common::{Comment, FieldOrComment, Handler, Identifier};
 
 #[derive(Debug)]
@@ -6,16 +8,33 @@ pub enum SettingsEntry {
     Handler(Handler),
     Class(Class),
     Id(Id),
+    Error(Span),
+}
+
+impl SettingsEntry {
+    pub fn span(&self) -> Span {
+        match self {
+            SettingsEntry::Comment(comment) => comment.0,
+            SettingsEntry::Handler(handler) => handler.span,
+            SettingsEntry::Class(class) => class.span,
+            SettingsEntry::Id(id) => id.span,
+            SettingsEntry::Error(span) => *span,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Class {
+    pub docs: Vec<Comment>,
     pub name: Identifier,
     pub binding: Vec<FieldOrComment>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Id {
+    pub docs: Vec<Comment>,
     pub name: Identifier,
     pub binding: Vec<FieldOrComment>,
+    pub span: Span,
 }
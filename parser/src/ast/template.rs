@@ -1,5 +1,7 @@
+use crate::lexer::Span;
+
 use super::{
-    common::{Comment, Field, Handler, Identifier},
+    common::{Comment, Field, Handler, Identifier, Receiver},
     expression::Expression,
 };
 
@@ -9,7 +11,25 @@ pub enum TemplateEntry {
     Tag(Tag),
     Loop(Loop),
     Conditional(Conditional),
-    Slot(Expression),
+    Slot(Slot),
+    Let(LetBinding),
+    Match(Match),
+    Error(Span),
+}
+
+impl TemplateEntry {
+    pub fn span(&self) -> Span {
+        match self {
+            TemplateEntry::Comment(comment) => comment.0,
+            TemplateEntry::Tag(tag) => tag.span,
+            TemplateEntry::Loop(for_loop) => for_loop.span,
+            TemplateEntry::Conditional(conditional) => conditional.span,
+            TemplateEntry::Slot(slot) => slot.span,
+            TemplateEntry::Let(binding) => binding.span,
+            TemplateEntry::Match(match_entry) => match_entry.span,
+            TemplateEntry::Error(span) => *span,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -17,19 +37,116 @@ pub struct Loop {
     pub pattern: MatchPattern,
     pub source: Expression,
     pub body: Vec<TemplateEntry>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Conditional {
+    /// The if branch, followed by any else if branches
+    pub branches: Vec<ConditionalBranch>,
+    /// The final else branch, if any
+    pub fallback: Option<ElseBranch>,
+    pub span: Span,
+}
+
+/// if cond {..} or else if cond {..}
+#[derive(Debug)]
+pub struct ConditionalBranch {
+    /// "if", or "else if"
+    pub keyword: Span,
     pub condition: Expression,
     pub body: Vec<TemplateEntry>,
+    pub span: Span,
+}
+
+/// else {..}
+#[derive(Debug)]
+pub struct ElseBranch {
+    /// "else"
+    pub keyword: Span,
+    pub body: Vec<TemplateEntry>,
+    pub span: Span,
+}
+
+/// slot(source), rendering the children passed to the component
+#[derive(Debug)]
+pub struct Slot {
+    pub source: Expression,
+    pub span: Span,
+}
+
+/// match value { Variant => {..} _ => {..} }
+#[derive(Debug)]
+pub struct Match {
+    pub value: Expression,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern: ArmPattern,
+    pub body: Vec<TemplateEntry>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum ArmPattern {
+    Variant(VariantPattern),
+    /// _
+    Wildcard(Span),
+}
+
+impl ArmPattern {
+    pub fn span(&self) -> Span {
+        match self {
+            ArmPattern::Variant(variant) => variant.span,
+            ArmPattern::Wildcard(span) => *span,
+        }
+    }
+}
+
+/// Enum::Variant, or just Variant, optionally binding its payload as in Variant(a, b)
+#[derive(Debug)]
+pub struct VariantPattern {
+    /// The enum name, None if left out
+    pub name: Option<Identifier>,
+    pub variant: Identifier,
+    pub bindings: Vec<Identifier>,
+    pub span: Span,
+}
+
+/// let name = value; where name is in scope for the siblings following it
+#[derive(Debug)]
+pub struct LetBinding {
+    pub name: Identifier,
+    pub value: Expression,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Tag {
+    /// Doc comments (///) directly preceding the tag
+    pub docs: Vec<Comment>,
     pub name: Identifier,
     pub attributes: Vec<Attribute>,
     pub body: Vec<TemplateEntry>,
+    /// The whole tag, from the opening "<" to the last ">"
+    pub span: Span,
+    /// "<name attributes..>", or the whole tag if self-closing
+    pub open_tag: Span,
+    /// "</name>", None if self-closing
+    pub close_tag: Option<Span>,
+}
+
+impl Tag {
+    /// The source between the opening and closing tag, None if self-closing
+    pub fn body_span(&self) -> Option<Span> {
+        self.close_tag.map(|close| Span {
+            start: self.open_tag.end,
+            end: close.start,
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -39,10 +156,22 @@ pub enum Attribute {
     Binding(Binding),
 }
 
+impl Attribute {
+    pub fn span(&self) -> Span {
+        match self {
+            Attribute::Handler(handler) => handler.span,
+            Attribute::Field(field) => field.span,
+            Attribute::Binding(binding) => binding.span,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Binding {
     pub key: Identifier,
+    pub receiver: Receiver,
     pub value: Identifier,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -50,3 +179,12 @@ pub enum MatchPattern {
     Identifier(Identifier),
     Tuple(Identifier, Identifier),
 }
+
+impl MatchPattern {
+    pub fn span(&self) -> Span {
+        match self {
+            MatchPattern::Identifier(ident) => ident.0,
+            MatchPattern::Tuple(first, second) => first.0.join(second.0),
+        }
+    }
+}
@@ -1,10 +1,16 @@
 use std::str::CharIndices;
+mod confusables;
+mod escape;
 mod span;
 mod token;
+pub use escape::unescape;
+use escape::{simple_escape, unicode_escape};
 pub use span::Span;
 pub use token::TokenKind;
 
-use crate::utils::MultiPeek;
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::{parser::ErrorKind, utils::MultiPeek, PaxParseError};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Token {
@@ -15,19 +21,43 @@ pub struct Token {
 pub struct TokenIterator<'src> {
     itr: MultiPeek<CharIndices<'src>>,
     pub src: &'src str,
+    /// Errors found while lexing, the offending characters are
+    /// either skipped or included in the closest matching token
+    errors: Vec<PaxParseError>,
+    /// Lexing an expression embedded in a string literal, where
+    /// strings are delimited by escaped quotes (\")
+    in_string: bool,
 }
 
 impl<'src> Iterator for TokenIterator<'src> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Token> {
+        loop {
+            if let Some(token) = self.next_or_skip()? {
+                return Some(token);
+            }
+        }
+    }
+}
+
+impl<'src> TokenIterator<'src> {
+    /// Lexes the next token, or returns Some(None) if an
+    /// unknown character was skipped instead
+    fn next_or_skip(&mut self) -> Option<Option<Token>> {
         //trim all white spaces
-        while let Some(_) = self.itr.next_if(|(_, c)| c.is_whitespace()) {}
+        while self.itr.next_if(|(_, c)| c.is_whitespace()).is_some() {}
 
-        let (_, char) = self.itr.peek().cloned()?;
-        Some(match char {
-            c if c.is_alphabetic() => self.ident_like(),
+        let (start, char) = self.itr.peek().cloned()?;
+        Some(Some(match char {
+            'r' if self.is_raw_string_next() => self.raw_string(),
+            '\\' if self.in_string && self.itr.peek_nth(1).is_some_and(|&(_, c)| c == '"') => {
+                self.escaped_string()
+            }
+            c if c == '_' || is_xid_start(c) => self.ident_like(),
+            v if v.is_ascii_digit() && self.src[..start].ends_with('#') => self.color_digits(),
             v if v.is_ascii_digit() => self.number(),
+            '.' if self.is_digit_at(1) => self.number(),
             '(' => self.single(TokenKind::OpenParenth),
             ')' => self.single(TokenKind::CloseParenth),
             '[' => self.single(TokenKind::OpenSquareBrack),
@@ -40,6 +70,7 @@ impl<'src> Iterator for TokenIterator<'src> {
             '@' => self.single(TokenKind::AtSymbol),
             ':' => self.one_or_two_if_with(TokenKind::Colon, ':', TokenKind::PathSep),
             ',' => self.single(TokenKind::Comma),
+            ';' => self.single(TokenKind::Semicolon),
             '#' => self.single(TokenKind::Hashtag),
             '^' => self.single(TokenKind::Exp),
             '/' => self.comment_or_slash(),
@@ -48,127 +79,282 @@ impl<'src> Iterator for TokenIterator<'src> {
             '%' => self.one_or_two_if_with(TokenKind::Percent, '%', TokenKind::Remainder),
             '<' => self.one_or_two_if_with(TokenKind::OpenAngBrack, '=', TokenKind::LessOrEq),
             '>' => self.one_or_two_if_with(TokenKind::CloseAngBrack, '=', TokenKind::MoreOrEq),
-            '=' => self.one_or_two_if_with(TokenKind::Assign, '=', TokenKind::Eq),
+            '=' => self.one_or_two_of(
+                TokenKind::Assign,
+                [('=', TokenKind::Eq), ('>', TokenKind::FatArrow)],
+            ),
             '!' => self.one_or_two_if_with(TokenKind::Not, '=', TokenKind::NotEq),
             '|' => self.one_or_two_if_with(TokenKind::VertLine, '|', TokenKind::Or),
             '&' => self.one_or_two_if_with(TokenKind::Ampersand, '&', TokenKind::And),
+            '?' => self.single(TokenKind::Question),
             c => {
-                eprintln!("unknown token: {:?}", c);
-                self.single(TokenKind::Unknown)
+                self.itr.next();
+                let span = Span {
+                    start,
+                    end: start + c.len_utf8(),
+                };
+                self.errors.push(
+                    PaxParseError::new(
+                        ErrorKind::UnknownCharacter,
+                        format!("unknown character {:?}", c),
+                    )
+                    .annotation(span, "not part of the pax syntax"),
+                );
+                return Some(None);
             }
-        })
+        }))
     }
-}
 
-impl<'src> TokenIterator<'src> {
     pub fn new(source: &'src str) -> Self {
         Self {
             itr: MultiPeek::new(source.char_indices()),
             src: source,
+            errors: Vec::new(),
+            in_string: false,
         }
     }
 
+    /// Lexes an expression embedded in a string literal, in the given part of the source.
+    /// Spans are still relative to the start of the source.
+    pub fn in_string(source: &'src str, range: Span) -> Self {
+        let source = &source[..range.end];
+        let mut itr = MultiPeek::new(source.char_indices());
+        while itr.next_if(|&(i, _)| i < range.start).is_some() {}
+        Self {
+            itr,
+            src: source,
+            errors: Vec::new(),
+            in_string: true,
+        }
+    }
+
+    pub fn take_errors(&mut self) -> Vec<PaxParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
     fn single(&mut self, token_type: TokenKind) -> Token {
         let Some((start, c)) = self.itr.next() else {
             unreachable!("already peeked to match on this function")
         };
         let end = start + c.len_utf8();
-        return Token {
+        Token {
             span: Span { start, end },
             kind: token_type,
-        };
+        }
     }
 
     fn ident_like(&mut self) -> Token {
-        let Some((start, _)) = self.itr.next() else {
+        let Some((start, c)) = self.itr.next() else {
             unreachable!("already peeked to match on this function")
         };
-        let mut end = start;
+        let mut end = start + c.len_utf8();
 
-        while let Some((i, _)) = self
-            .itr
-            .next_if(|(_, c)| c.is_ascii_alphabetic() || c.is_ascii_digit() || c == &'_')
-        {
-            end = i;
+        // identifiers as defined by UAX #31 (with leading underscores allowed)
+        while let Some((i, c)) = self.itr.next_if(|&(_, c)| is_xid_continue(c)) {
+            end = i + c.len_utf8();
         }
-        end += 1;
         let src = &self.src[start..end];
-
-        // Trim "self." and "this." in the entire file,
-        // instead returning the identifier directly afterwards
-        if matches!(src, "self" | "this") {
-            if self.itr.next_if(|&(_, c)| c == '.').is_some() {
-                // read the next ident instead
-                return self.ident_like();
-            }
+        if !src.is_ascii() && src.chars().any(|c| c.is_ascii_alphabetic()) {
+            self.lint_confusables(start, src);
         }
-
         let token_type = match src {
             "for" => TokenKind::For,
             "if" => TokenKind::If,
-            "slot" => TokenKind::Slot,
-            "in" => TokenKind::In,
-            "px" => TokenKind::Pixels,
-            "deg" => TokenKind::Degrees,
-            "rad" => TokenKind::Radians,
-            "bind" => TokenKind::Bind,
             _ => TokenKind::Identifier,
         };
-        return Token {
+        Token {
             span: Span { start, end },
             kind: token_type,
-        };
+        }
+    }
+
+    /// Warns about letters in a (partly ASCII) identifier that look like
+    /// ASCII letters, such as a Cyrillic "е" in "Rеctangle"
+    fn lint_confusables(&mut self, start: usize, ident: &str) {
+        for (i, c) in ident.char_indices() {
+            let Some(ascii) = confusables::ascii_lookalike(c) else {
+                continue;
+            };
+            let span = Span {
+                start: start + i,
+                end: start + i + c.len_utf8(),
+            };
+            self.errors.push(
+                PaxParseError::new(
+                    ErrorKind::ConfusableCharacter,
+                    format!(
+                        "identifier contains a character that looks like {:?}",
+                        ascii
+                    ),
+                )
+                .annotation(
+                    span,
+                    format!("U+{:04X} is not the ASCII letter {:?}", c as u32, ascii),
+                )
+                .suggestion(
+                    span,
+                    ascii.to_string(),
+                    format!("replace it with the ASCII letter {:?}", ascii),
+                ),
+            );
+        }
     }
 
     fn number(&mut self) -> Token {
-        let Some((start, _)) = self.itr.next() else {
+        let Some(&(start, first)) = self.itr.peek() else {
             unreachable!("already peeked to match on this function")
         };
-
-        let mut end = start;
-        while let Some((i, _)) = self.itr.next_if(|(_, c)| c.is_ascii_digit()) {
-            end = i;
+        let prefix = self.itr.peek_nth(1).map(|&(_, c)| c);
+        match (first, prefix) {
+            ('0', Some('x' | 'X')) => return self.radix_number(start, 16),
+            ('0', Some('b' | 'B')) => return self.radix_number(start, 2),
+            _ => (),
         }
-        let is_float_next = self.itr.peek().is_some_and(|&(_, c)| c == '.')
-            && self
-                .itr
-                .peek_nth(1)
-                .is_some_and(|&(_, c)| c.is_ascii_digit());
 
-        if is_float_next {
+        // empty for floats starting with '.'
+        let mut end = self.digits(start);
+
+        let has_fraction = self.itr.peek().is_some_and(|&(_, c)| c == '.') && self.is_digit_at(1);
+        if has_fraction {
             // skip '.'
             self.itr.next();
-            while let Some((i, _)) = self.itr.next_if(|(_, c)| c.is_ascii_digit()) {
-                end = i;
+            end = self.digits(end + 1);
+            let is_malformed =
+                self.itr.peek().is_some_and(|&(_, c)| c == '.') && self.is_digit_at(1);
+            if is_malformed {
+                // more than one decimal point, such as "1.2.3"
+                while let Some((i, _)) = self.itr.next_if(|&(_, c)| c.is_ascii_digit() || c == '.')
+                {
+                    end = i + 1;
+                }
+                self.errors.push(
+                    PaxParseError::new(ErrorKind::MalformedNumber, "malformed number").annotation(
+                        Span { start, end },
+                        "a number can contain at most one decimal point",
+                    ),
+                );
+            }
+        }
+
+        // an exponent such as 1e-3, but not a unit starting with "e"
+        let has_sign = self
+            .itr
+            .peek_nth(1)
+            .is_some_and(|&(_, c)| c == '-' || c == '+');
+        let has_exponent = self.itr.peek().is_some_and(|&(_, c)| c == 'e' || c == 'E')
+            && self.is_digit_at(1 + has_sign as usize);
+        if has_exponent {
+            // skip 'e' and sign
+            self.itr.next();
+            if has_sign {
+                self.itr.next();
             }
+            end = self.digits(end);
         }
-        end += 1;
+
         Token {
             span: Span { start, end },
-            kind: if is_float_next {
-                TokenKind::Float
-            } else {
-                TokenKind::Integer
+            kind: match has_fraction || has_exponent {
+                true => TokenKind::Float,
+                false => TokenKind::Integer,
             },
         }
     }
 
+    /// The hex digits of a color such as #0b2233, lexed as a single integer token instead of
+    /// a number, which could be malformed ("0b2233") or split up ("1e3" + "fff")
+    fn color_digits(&mut self) -> Token {
+        let Some(&(start, _)) = self.itr.peek() else {
+            unreachable!("already peeked to match on this function")
+        };
+        let mut end = start;
+        while let Some((i, c)) = self.itr.next_if(|&(_, c)| c.is_ascii_alphanumeric()) {
+            end = i + c.len_utf8();
+        }
+        Token {
+            span: Span { start, end },
+            kind: TokenKind::Integer,
+        }
+    }
+
+    fn is_digit_at(&mut self, i: usize) -> bool {
+        self.itr
+            .peek_nth(i)
+            .is_some_and(|&(_, c)| c.is_ascii_digit())
+    }
+
+    /// Consumes digits and digit separators, returning the end of the last one consumed
+    fn digits(&mut self, mut end: usize) -> usize {
+        while let Some((i, _)) = self.itr.next_if(|&(_, c)| c.is_ascii_digit() || c == '_') {
+            end = i + 1;
+        }
+        end
+    }
+
+    /// Integer with a prefix such as 0xFF or 0b1010
+    fn radix_number(&mut self, start: usize, radix: u32) -> Token {
+        // skip prefix
+        self.itr.next();
+        self.itr.next();
+        let digits_start = start + 2;
+        let mut end = digits_start;
+        // all decimal digits are consumed for binary numbers, to be able to report them
+        while let Some((i, _)) = self
+            .itr
+            .next_if(|&(_, c)| c.is_digit(radix.max(10)) || c == '_')
+        {
+            end = i + 1;
+        }
+        let span = Span { start, end };
+        let name = match radix {
+            16 => "hexadecimal",
+            _ => "binary",
+        };
+        let digits = &self.src[digits_start..end];
+        if digits.chars().all(|c| c == '_') {
+            self.errors.push(
+                PaxParseError::new(ErrorKind::MalformedNumber, "malformed number")
+                    .annotation(span, format!("expected {} digits after the prefix", name)),
+            );
+        } else if let Some(i) = digits.find(|c: char| !c.is_digit(radix) && c != '_') {
+            let digit = Span {
+                start: digits_start + i,
+                end: digits_start + i + 1,
+            };
+            self.errors.push(
+                PaxParseError::new(ErrorKind::MalformedNumber, "malformed number")
+                    .annotation(digit, format!("invalid digit for a {} number", name))
+                    .annotation(span, "in this number"),
+            );
+        }
+        Token {
+            span,
+            kind: TokenKind::Integer,
+        }
+    }
+
     fn comment_or_slash(&mut self) -> Token {
         let Some((start, _)) = self.itr.next() else {
             unreachable!("already peeked to match on this function")
         };
 
-        let mut end = start;
-        let token_type = match self.itr.peek() {
+        let (token_type, end) = match self.itr.peek() {
             Some(&(_, '/')) => {
-                while let Some((i, _)) = self.itr.next_if(|&(_, c)| c != '\n') {
-                    end = i;
+                // "///" starts a doc comment, but "////" is a regular comment
+                let is_doc = self.itr.peek_nth(1).is_some_and(|&(_, c)| c == '/')
+                    && self.itr.peek_nth(2).is_none_or(|&(_, c)| c != '/');
+                let mut end = start + 1;
+                while let Some((i, c)) = self.itr.next_if(|&(_, c)| c != '\n') {
+                    end = i + c.len_utf8();
+                }
+                match is_doc {
+                    true => (TokenKind::DocComment, end),
+                    false => (TokenKind::Comment, end),
                 }
-                TokenKind::Comment
             }
-            _ => TokenKind::Slash,
+            Some(&(_, '*')) => (TokenKind::Comment, self.block_comment(start)),
+            _ => (TokenKind::Slash, start + 1),
         };
-        end += 1;
 
         Token {
             span: Span { start, end },
@@ -176,14 +362,75 @@ impl<'src> TokenIterator<'src> {
         }
     }
 
+    /// Consumes a block comment (which can contain nested block comments)
+    /// where the starting "/" has already been consumed, returning its end.
+    fn block_comment(&mut self, start: usize) -> usize {
+        // skip '*'
+        self.itr.next();
+        let mut end = start + 2;
+        let mut depth = 1;
+        while depth > 0 {
+            let Some((i, c)) = self.itr.next() else {
+                let help = match depth {
+                    1 => "add a closing */ at the end of the comment".to_owned(),
+                    n => format!("comments can be nested, add {} closing */", n),
+                };
+                self.errors.push(
+                    PaxParseError::new(
+                        ErrorKind::UnterminatedComment,
+                        "unterminated block comment",
+                    )
+                    .annotation(
+                        Span {
+                            start,
+                            end: start + 2,
+                        },
+                        "comment starts here but is never closed",
+                    )
+                    .help(help),
+                );
+                break;
+            };
+            end = i + c.len_utf8();
+            match c {
+                '/' if self.itr.next_if(|&(_, c)| c == '*').is_some() => {
+                    depth += 1;
+                    end += 1;
+                }
+                '*' if self.itr.next_if(|&(_, c)| c == '/').is_some() => {
+                    depth -= 1;
+                    end += 1;
+                }
+                _ => (),
+            }
+        }
+        end
+    }
+
     fn one_or_two_if_with(&mut self, if_one: TokenKind, if_with: char, if_two: TokenKind) -> Token {
+        self.one_or_two_of(if_one, [(if_with, if_two)])
+    }
+
+    /// Lexes a single character, or two if the second one is any of the given ones
+    /// (such as "=", "==" or "=>"), with the kind paired with it
+    fn one_or_two_of<const N: usize>(
+        &mut self,
+        if_one: TokenKind,
+        if_two: [(char, TokenKind); N],
+    ) -> Token {
         let Some((start, character)) = self.itr.next() else {
             unreachable!("already peeked to match on this function")
         };
-        let (token_type, end) = if let Some((i, _)) = self.itr.next_if(|&(_, c)| c == if_with) {
-            (if_two, i)
-        } else {
-            (if_one, start + character.len_utf8())
+        let second = self
+            .itr
+            .peek()
+            .and_then(|&(i, c)| Some((i + c.len_utf8(), if_two.iter().find(|t| t.0 == c)?.1)));
+        let (token_type, end) = match second {
+            Some((end, token_type)) => {
+                self.itr.next();
+                (token_type, end)
+            }
+            None => (if_one, start + character.len_utf8()),
         };
         Token {
             span: Span { start, end },
@@ -196,30 +443,211 @@ impl<'src> TokenIterator<'src> {
             unreachable!("already peeked to match on this function")
         };
 
-        let mut end = start;
-        let mut esc = false;
-        loop {
-            let Some((i, c)) = self.itr.next() else { break };
-            end = i;
+        let mut end = None;
+        while let Some((i, c)) = self.itr.next() {
             match c {
-                '\\' => {
-                    esc = !esc;
-                    continue;
-                }
                 '"' => {
-                    if !esc {
-                        break;
-                    }
+                    end = Some(i + 1);
+                    break;
                 }
+                '\\' => self.escape(i),
                 _ => (),
-            };
-            esc = false;
+            }
+        }
+        let end = end.unwrap_or_else(|| {
+            self.errors.push(
+                PaxParseError::new(ErrorKind::UnterminatedString, "unterminated string")
+                    .annotation(
+                        Span {
+                            start,
+                            end: start + 1,
+                        },
+                        "string starts here but is never closed",
+                    )
+                    .help("add a closing \" at the end of the string"),
+            );
+            self.src.len()
+        });
+
+        Token {
+            span: Span { start, end },
+            kind: TokenKind::String,
+        }
+    }
+
+    /// A string inside an expression embedded in another string, such as \"bob\"
+    /// in "{name == \"bob\"}". Escapes were already checked when lexing the outer string.
+    fn escaped_string(&mut self) -> Token {
+        let Some((start, _)) = self.itr.next() else {
+            unreachable!("already peeked to match on this function")
+        };
+        // skip '"'
+        self.itr.next();
+        let mut end = None;
+        while let Some((_, c)) = self.itr.next() {
+            if c != '\\' {
+                continue;
+            }
+            if let Some((i, _)) = self.itr.next_if(|&(_, c)| c == '"') {
+                end = Some(i + 1);
+                break;
+            }
+            self.itr.next();
+        }
+        let end = end.unwrap_or_else(|| {
+            self.errors.push(
+                PaxParseError::new(ErrorKind::UnterminatedString, "unterminated string")
+                    .annotation(
+                        Span {
+                            start,
+                            end: start + 2,
+                        },
+                        "string starts here but is never closed",
+                    )
+                    .help("strings inside embedded expressions are closed with \\\""),
+            );
+            self.src.len()
+        });
+        Token {
+            span: Span { start, end },
+            kind: TokenKind::String,
+        }
+    }
+
+    /// Checks for r"..." or r#"..."# (with any number of #)
+    fn is_raw_string_next(&mut self) -> bool {
+        let mut i = 1;
+        while self.itr.peek_nth(i).is_some_and(|&(_, c)| c == '#') {
+            i += 1;
         }
-        end += 1;
+        self.itr.peek_nth(i).is_some_and(|&(_, c)| c == '"')
+    }
+
+    /// Raw strings can't contain escapes, but can contain quotes
+    /// that aren't followed by as many # as the string started with
+    fn raw_string(&mut self) -> Token {
+        let Some((start, _)) = self.itr.next() else {
+            unreachable!("already peeked to match on this function")
+        };
+        let mut hashes = 0;
+        while self.itr.next_if(|&(_, c)| c == '#').is_some() {
+            hashes += 1;
+        }
+        // skip '"'
+        self.itr.next();
+
+        let mut end = None;
+        while let Some((i, c)) = self.itr.next() {
+            if c != '"' {
+                continue;
+            }
+            let mut closing_hashes = 0;
+            while closing_hashes < hashes && self.itr.next_if(|&(_, c)| c == '#').is_some() {
+                closing_hashes += 1;
+            }
+            if closing_hashes == hashes {
+                end = Some(i + 1 + hashes);
+                break;
+            }
+        }
+        let end = end.unwrap_or_else(|| {
+            self.errors.push(
+                PaxParseError::new(ErrorKind::UnterminatedString, "unterminated raw string")
+                    .annotation(
+                        Span {
+                            start,
+                            end: start + 2 + hashes,
+                        },
+                        "raw string starts here but is never closed",
+                    )
+                    .help(format!(
+                        "add a closing \"{} at the end of the string",
+                        "#".repeat(hashes)
+                    )),
+            );
+            self.src.len()
+        });
 
         Token {
             span: Span { start, end },
             kind: TokenKind::String,
         }
     }
+
+    /// Validates the escape sequence after a backslash at `start` inside a string
+    fn escape(&mut self, start: usize) {
+        let Some((i, c)) = self.itr.next() else {
+            // reported as an unterminated string
+            return;
+        };
+        let mut end = i + c.len_utf8();
+        let valid = match c {
+            'u' => {
+                // unicode escape such as \u{1F600}
+                if let Some((j, _)) = self.itr.next_if(|&(_, c)| c == '{') {
+                    end = j + 1;
+                    while let Some((j, c)) = self
+                        .itr
+                        .next_if(|&(_, c)| c.is_ascii_hexdigit() || c == '}')
+                    {
+                        end = j + 1;
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+                unicode_escape(&self.src[i..end]).is_some()
+            }
+            c => simple_escape(c).is_some(),
+        };
+        if !valid {
+            let span = Span { start, end };
+            let err = match c {
+                'u' => PaxParseError::new(ErrorKind::InvalidEscape, "invalid unicode escape")
+                    .annotation(span, "invalid unicode escape")
+                    .help("unicode escapes are written as \\u{..} with 1 to 6 hex digits, such as \\u{1F600}"),
+                _ => PaxParseError::new(
+                    ErrorKind::InvalidEscape,
+                    format!("unknown escape sequence \\{}", c),
+                )
+                .annotation(span, "unknown escape sequence")
+                .help("valid escapes are \\n, \\r, \\t, \\0, \\\\, \\\", \\' and \\u{..}")
+                .suggestion(
+                    Span {
+                        start,
+                        end: start + 1,
+                    },
+                    "\\\\",
+                    "escape the backslash to include it in the string",
+                ),
+            };
+            self.errors.push(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TokenIterator, TokenKind};
+
+    #[test]
+    fn test_token_spans() {
+        let source = "a == b => c <= d :: e..f = !g";
+        let tokens: Vec<_> = TokenIterator::new(source)
+            .map(|token| (token.kind, &source[token.span.as_range()]))
+            .filter(|(kind, _)| *kind != TokenKind::Identifier)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::Eq, "=="),
+                (TokenKind::FatArrow, "=>"),
+                (TokenKind::LessOrEq, "<="),
+                (TokenKind::PathSep, "::"),
+                (TokenKind::Range, ".."),
+                (TokenKind::Assign, "="),
+                (TokenKind::Not, "!"),
+            ]
+        );
+    }
 }
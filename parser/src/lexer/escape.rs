@@ -0,0 +1,63 @@
+/// The character a single character escape (such as \n) stands for
+pub fn simple_escape(c: char) -> Option<char> {
+    Some(match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        '\'' => '\'',
+        _ => return None,
+    })
+}
+
+/// Decodes the escape sequences in the content of a (non raw) string.
+/// Invalid escapes are reported while lexing, and are kept as is.
+pub fn unescape(content: &str) -> String {
+    let mut value = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let rest = chars.as_str();
+        let Some(escaped) = chars.next() else {
+            value.push(c);
+            break;
+        };
+        if let Some(decoded) = simple_escape(escaped) {
+            value.push(decoded);
+        } else if let Some(decoded) = unicode_escape(rest) {
+            value.push(decoded);
+            // skip "u{..}"
+            chars = rest[rest.find('}').unwrap_or(rest.len()) + 1..].chars();
+        } else {
+            value.push(c);
+            value.push(escaped);
+        }
+    }
+    value
+}
+
+/// Decodes a unicode escape such as "u{1F600}" (the part after the backslash)
+pub fn unicode_escape(escape: &str) -> Option<char> {
+    let digits = escape.strip_prefix("u{")?.split_once('}')?.0;
+    if !(1..=6).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(digits, 16).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r#"a\nb\t\"c\"\\"#), "a\nb\t\"c\"\\");
+        assert_eq!(unescape(r"smile: \u{1F600}!"), "smile: 😀!");
+        assert_eq!(unescape(r"\q \u{} \u{110000}"), r"\q \u{} \u{110000}");
+    }
+}
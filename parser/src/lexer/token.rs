@@ -16,21 +16,30 @@ pub enum TokenKind {
     AtSymbol,
     Identifier,
     For,
+    /// contextual keyword, see TokenKind::contextual_keyword
     In,
     If,
+    /// contextual keyword
+    Else,
+    /// contextual keyword
+    True,
+    /// contextual keyword
+    False,
+    /// contextual keyword, none or null
+    None,
     Percent,
     Integer,
     Float,
     Period,
     Colon,
+    /// contextual keyword
     Slot,
+    #[allow(clippy::upper_case_acronyms)]
     EOF,
     #[default]
     Unknown,
-    Pixels,
-    Degrees,
-    Radians,
     Comment,
+    DocComment,
     Comma,
     String,
     Hashtag,
@@ -46,10 +55,38 @@ pub enum TokenKind {
     VertLine,
     Or,
     Ampersand,
+    Question,
     And,
     Exp,
     PathSep,
+    /// contextual keyword
     Bind,
+    /// contextual keyword
+    Let,
+    Semicolon,
+    /// contextual keyword
+    Match,
+    FatArrow,
+}
+
+impl TokenKind {
+    /// Keywords that are only keywords in some positions, and otherwise identifiers.
+    /// These are never produced by the lexer, instead the parser checks the text of
+    /// identifiers where they are expected.
+    pub fn contextual_keyword(self) -> Option<&'static str> {
+        match self {
+            TokenKind::In => Some("in"),
+            TokenKind::Slot => Some("slot"),
+            TokenKind::Bind => Some("bind"),
+            TokenKind::Let => Some("let"),
+            TokenKind::Match => Some("match"),
+            TokenKind::Else => Some("else"),
+            TokenKind::True => Some("true"),
+            TokenKind::False => Some("false"),
+            TokenKind::None => Some("none"),
+            _ => None,
+        }
+    }
 }
 
 impl Display for TokenKind {
@@ -71,6 +108,10 @@ impl Display for TokenKind {
             TokenKind::For => "for",
             TokenKind::In => "in",
             TokenKind::If => "if",
+            TokenKind::Else => "else",
+            TokenKind::True => "true",
+            TokenKind::False => "false",
+            TokenKind::None => "none",
             TokenKind::Percent => "\"%\"",
             TokenKind::Integer => "integer",
             TokenKind::Float => "float",
@@ -79,10 +120,8 @@ impl Display for TokenKind {
             TokenKind::Slot => "slot",
             TokenKind::EOF => "end of line",
             TokenKind::Unknown => "<UNKNOWN>",
-            TokenKind::Pixels => "px",
-            TokenKind::Degrees => "deg",
-            TokenKind::Radians => "rad",
             TokenKind::Comment => "comment",
+            TokenKind::DocComment => "doc comment",
             TokenKind::Comma => "\",\"",
             TokenKind::String => "string",
             TokenKind::Hashtag => "\"#\"",
@@ -98,10 +137,15 @@ impl Display for TokenKind {
             TokenKind::VertLine => "\"|\"",
             TokenKind::Or => "\"||\"",
             TokenKind::Ampersand => "\"&\"",
+            TokenKind::Question => "\"?\"",
             TokenKind::And => "\"&&\"",
             TokenKind::Exp => "\"^\"",
             TokenKind::PathSep => "\"::\"",
             TokenKind::Bind => "binding",
+            TokenKind::Let => "let",
+            TokenKind::Semicolon => "\";\"",
+            TokenKind::Match => "match",
+            TokenKind::FatArrow => "\"=>\"",
         };
         write!(f, "{}", s)
     }
@@ -1,6 +1,12 @@
 mod lexer;
 mod parser;
+mod source_map;
 mod utils;
 
 pub mod ast;
-pub use parser::Parser;
+pub use lexer::Span;
+pub use parser::{
+    explain, to_sarif, to_sarif_with_source_map, ColorMode, ErrorKind, Parser, PaxParseError,
+    Severity, Suggestion,
+};
+pub use source_map::{FileId, LineColumn, SourceMap};
@@ -1,23 +1,66 @@
 use std::error::Error;
 
-use pax_parser::Parser;
+use pax_parser::{explain, to_sarif_with_source_map, SourceMap};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // read a files source
-    let file_name = std::env::args()
-        .skip(1)
-        .next()
-        .unwrap_or("test_file.pax".to_owned());
-    let source = std::fs::read_to_string(&file_name).unwrap();
+    let mut args = std::env::args().skip(1);
+    let mut file_names = vec![];
+    let mut message_format = String::from("human");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // print the long form description of an error code
+            "--explain" => {
+                let code = args.next().ok_or("--explain expects an error code")?;
+                let explanation =
+                    explain(&code).ok_or_else(|| format!("{} is not a valid error code", code))?;
+                println!("{}", explanation);
+                return Ok(());
+            }
+            "--message-format" => {
+                message_format = args
+                    .next()
+                    .ok_or("--message-format expects human, json or sarif")?;
+            }
+            _ => file_names.push(arg),
+        }
+    }
+    if file_names.is_empty() {
+        file_names.push("test_file.pax".to_owned());
+    }
 
-    // parse it into a pax AST
-    let ast = Parser::new(&source).pax();
+    // read the sources of all files
+    let mut files = SourceMap::new();
+    for file_name in file_names {
+        let source = std::fs::read_to_string(&file_name).unwrap();
+        files.add(file_name, source);
+    }
+
+    // parse them into pax ASTs, recovering from errors
+    let mut errors = vec![];
+    for file in files.file_ids() {
+        let (ast, file_errors) = files.parse(file);
+        if message_format == "human" {
+            println!("-------DONE-------");
+            println!("parsed AST of {}: {:#?}", files.name(file), ast);
+        }
+        errors.extend(file_errors);
+    }
 
     // print results
-    println!("-------DONE-------");
-    match ast {
-        Ok(ast) => println!("parsed AST: {:#?}", ast),
-        Err(e) => e.print_with_file(&file_name, &source)?,
-    };
+    match message_format.as_str() {
+        "human" => {
+            for e in errors {
+                e.print_with_source_map(&files)?;
+            }
+        }
+        // one diagnostic per line
+        "json" => {
+            for e in errors {
+                println!("{}", e.to_json_with_source_map(&files)?);
+            }
+        }
+        "sarif" => println!("{}", to_sarif_with_source_map(&errors, &files)?),
+        other => return Err(format!("unknown message format {:?}", other).into()),
+    }
     Ok(())
 }
@@ -1,21 +1,33 @@
-use crate::ast::PaxAst;
+use crate::ast::{
+    common::Comment, expression::Unit, settings::SettingsEntry, template::TemplateEntry, PaxAst,
+};
 use crate::lexer::{Span, Token, TokenIterator, TokenKind};
 use crate::utils::MultiPeek;
 
-use self::errors::PaxParseError;
+pub use self::errors::{
+    explain, to_sarif, to_sarif_with_source_map, ColorMode, ErrorKind, PaxParseError, Severity,
+    Suggestion,
+};
 
 pub mod common;
 mod errors;
 pub mod expression;
 pub mod literal;
 pub mod settings;
+pub mod string;
 pub mod template;
 pub mod value;
 
 /// Parses a pax source file into an AST.
 pub struct Parser<'src> {
     tokens: MultiPeek<TokenIterator<'src>>,
-    context_stack: Vec<&'static str>,
+    /// Description of each grammar rule currently being parsed,
+    /// together with the span of the token it started at
+    context_stack: Vec<(&'static str, Span)>,
+    errors: Vec<PaxParseError>,
+    /// Names of the tags whose bodies are currently being parsed
+    open_tags: Vec<Span>,
+    prev_token_end: usize,
 }
 
 impl<'src> Parser<'src> {
@@ -23,34 +35,84 @@ impl<'src> Parser<'src> {
         Self {
             tokens: MultiPeek::new(TokenIterator::new(source)),
             context_stack: Vec::new(),
+            errors: Vec::new(),
+            open_tags: Vec::new(),
+            prev_token_end: 0,
         }
     }
 
+    /// Parser for an expression embedded in the given range of the source
+    /// (such as in an interpolated string), inheriting the current context.
+    fn embedded(&self, range: Span) -> Parser<'src> {
+        Parser {
+            tokens: MultiPeek::new(TokenIterator::in_string(self.tokens.inner().src, range)),
+            context_stack: self.context_stack.clone(),
+            errors: Vec::new(),
+            open_tags: Vec::new(),
+            prev_token_end: range.start,
+        }
+    }
+
+    /// Parses the source, returning the first error encountered if any.
+    /// Warnings are ignored, use pax_recovering to get them.
     pub fn pax(&mut self) -> Result<PaxAst, PaxParseError> {
+        let (ast, errors) = self.pax_recovering();
+        match errors.into_iter().find(|e| e.severity() == Severity::Error) {
+            Some(error) => Err(error),
+            None => Ok(ast),
+        }
+    }
+
+    /// Parses the source, recovering from errors instead of stopping at the first one.
+    /// Returns a (possibly partial) AST where the unparsable parts are replaced by error
+    /// nodes, together with all errors encountered in source order.
+    pub fn pax_recovering(&mut self) -> (PaxAst, Vec<PaxParseError>) {
         let mut templates = vec![];
         let mut settings = vec![];
         loop {
+            let start = self.peek_span().start;
             match self.peek_token() {
                 TokenKind::OpenAngBrack
                 | TokenKind::For
                 | TokenKind::If
-                | TokenKind::Slot
-                | TokenKind::Comment => templates.extend(self.template()?),
-                TokenKind::AtSymbol => settings.extend(self.settings()?),
+                | TokenKind::Comment
+                | TokenKind::DocComment => templates.extend(self.template()),
+                TokenKind::Identifier
+                    if self.is_keyword_next(TokenKind::Slot)
+                        || self.is_keyword_next(TokenKind::Match)
+                        || self.is_let_next() =>
+                {
+                    templates.extend(self.template())
+                }
+                TokenKind::AtSymbol => match self.settings() {
+                    Ok(entries) => settings.extend(entries),
+                    Err(e) => settings.push(SettingsEntry::Error(self.recover(
+                        e,
+                        start,
+                        Self::is_top_level_boundary,
+                    ))),
+                },
                 TokenKind::EOF => break,
                 _ => {
-                    return Err(self.error([
-                        TokenKind::OpenAngBrack,
-                        TokenKind::AtSymbol,
-                        TokenKind::EOF,
-                    ]))
+                    let err =
+                        self.error([TokenKind::OpenAngBrack, TokenKind::AtSymbol, TokenKind::EOF]);
+                    templates.push(TemplateEntry::Error(self.recover(
+                        err,
+                        start,
+                        Self::is_top_level_boundary,
+                    )));
                 }
             };
         }
-        Ok(PaxAst {
+        let ast = PaxAst {
             templates,
             settings,
-        })
+        };
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.append(&mut self.tokens.inner_mut().take_errors());
+        // lexer errors are found ahead of the parser (when peeking), keep them in source order
+        errors.sort_by_key(|e| e.span().map(|span| span.start));
+        (ast, errors)
     }
 
     pub fn is_at_eof(&mut self) -> bool {
@@ -65,25 +127,161 @@ impl<'src> Parser<'src> {
         self.tokens.peek_nth(i).map_or(TokenKind::EOF, |t| t.kind)
     }
 
+    /// Checks if the next token is the given keyword. Contextual keywords (such as slot)
+    /// are lexed as identifiers, so that they can still be used as names elsewhere.
+    fn is_keyword_next(&mut self, keyword: TokenKind) -> bool {
+        let Some(text) = keyword.contextual_keyword() else {
+            return self.peek_token() == keyword;
+        };
+        let span = self.peek_span();
+        self.peek_token() == TokenKind::Identifier && self.source_of(span) == text
+    }
+
+    /// The unit following a number or expression, such as px in 5px. Units aren't
+    /// reserved either: px=5 is still an attribute, and px: 5 a field, unless the unit
+    /// directly follows the previous token (as in c ? 1px : 2px).
+    fn peek_unit(&mut self) -> Option<Unit> {
+        match self.peek_token() {
+            TokenKind::Percent => Some(Unit::Percent),
+            TokenKind::Identifier => {
+                let span = self.peek_span();
+                let is_key = matches!(self.peek_nth_token(1), TokenKind::Assign | TokenKind::Colon);
+                if is_key && span.start != self.prev_token_end {
+                    return None;
+                }
+                Unit::from_suffix(self.source_of(span))
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of consecutive doc comments starting at the next token
+    fn doc_comment_count(&mut self) -> usize {
+        let mut count = 0;
+        while self.peek_nth_token(count) == TokenKind::DocComment {
+            count += 1;
+        }
+        count
+    }
+
+    fn doc_comments(&mut self) -> Vec<Comment> {
+        let mut docs = vec![];
+        while let Some(token) = self.next_token_if(|t| t == TokenKind::DocComment) {
+            docs.push(Comment(token.span));
+        }
+        docs
+    }
+
+    fn peek_span(&mut self) -> Span {
+        match self.tokens.peek() {
+            Some(t) => t.span,
+            None => self.eof_span(),
+        }
+    }
+
     fn next_token_if(&mut self, f: impl FnOnce(TokenKind) -> bool) -> Option<Token> {
-        self.tokens.next_if(|t| f(t.kind))
+        let token = self.tokens.next_if(|t| f(t.kind))?;
+        self.prev_token_end = token.span.end;
+        Some(token)
     }
 
     fn next_token(&mut self) -> Token {
-        self.tokens.next().unwrap_or_else(|| {
-            let len = self.tokens.inner().src.len();
-            Token {
-                span: Span {
-                    start: len - 1,
-                    end: len,
-                },
+        match self.tokens.next() {
+            Some(token) => {
+                self.prev_token_end = token.span.end;
+                token
+            }
+            None => Token {
+                span: self.eof_span(),
                 kind: TokenKind::EOF,
+            },
+        }
+    }
+
+    /// Span from start up until the end of the last consumed token
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.prev_token_end.max(start),
+        }
+    }
+
+    /// Empty span at the end of the source, which (unlike the last
+    /// character) can't start in the middle of a multibyte character
+    fn eof_span(&self) -> Span {
+        let len = self.tokens.inner().src.len();
+        Span {
+            start: len,
+            end: len,
+        }
+    }
+
+    /// Records an error and skips tokens until `is_boundary` returns true
+    /// for a token that isn't nested inside brackets opened while skipping,
+    /// so that parsing can resume from a known state. Returns the span
+    /// from `start` to the end of the skipped tokens.
+    fn recover(
+        &mut self,
+        error: PaxParseError,
+        start: usize,
+        is_boundary: fn(&mut Self) -> bool,
+    ) -> Span {
+        self.errors.push(error);
+        // always make progress, otherwise the caller would fail on the same token again
+        if self.prev_token_end <= start {
+            self.next_token();
+        }
+        let mut depth = 0usize;
+        loop {
+            match self.peek_token() {
+                TokenKind::EOF => break,
+                _ if depth == 0 && is_boundary(self) => break,
+                TokenKind::OpenCurlBrack | TokenKind::OpenParenth | TokenKind::OpenSquareBrack => {
+                    depth += 1
+                }
+                TokenKind::CloseCurlBrack
+                | TokenKind::CloseParenth
+                | TokenKind::CloseSquareBrack => depth = depth.saturating_sub(1),
+                _ => (),
             }
-        })
+            self.next_token();
+        }
+        self.span_from(start)
+    }
+
+    fn is_tag_start_next(&mut self) -> bool {
+        self.peek_token() == TokenKind::OpenAngBrack
+            && matches!(
+                self.peek_nth_token(1),
+                TokenKind::Identifier | TokenKind::Slash
+            )
+    }
+
+    fn is_settings_block_next(&mut self) -> bool {
+        self.peek_token() == TokenKind::AtSymbol
+            && self.peek_nth_token(1) == TokenKind::Identifier
+            && self.peek_nth_token(2) == TokenKind::OpenCurlBrack
+    }
+
+    fn is_top_level_boundary(&mut self) -> bool {
+        self.is_tag_start_next()
+            || self.is_settings_block_next()
+            || self.is_keyword_next(TokenKind::Slot)
+            || self.is_keyword_next(TokenKind::Match)
+            || self.is_let_next()
+            || matches!(
+                self.peek_token(),
+                TokenKind::For | TokenKind::If | TokenKind::Comment | TokenKind::DocComment
+            )
+    }
+
+    fn is_template_boundary(&mut self) -> bool {
+        self.is_top_level_boundary() || self.peek_token() == TokenKind::CloseCurlBrack
     }
 
     fn push_context(&mut self, context: &'static str) {
-        self.context_stack.push(context);
+        let span = self.peek_span();
+        self.context_stack.push((context, span));
         // println!(
         //     "{}entered: {:?}",
         //     " ".repeat(self.context_stack.len()),
@@ -100,14 +298,46 @@ impl<'src> Parser<'src> {
         self.context_stack.pop();
     }
 
-    fn source_of(&self, span: Span) -> &str {
+    fn source_of(&self, span: Span) -> &'src str {
         &self.tokens.inner().src[span.as_range()]
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Parser;
+    use crate::{
+        ast::{
+            common::{Field, FieldOrComment, Receiver},
+            expression::{BinaryOp, BoolLiteral, Expression, StringSegment, Unit, Value},
+            settings::SettingsEntry,
+            template::{ArmPattern, Attribute, Tag, TemplateEntry},
+        },
+        ErrorKind, Parser, PaxParseError, Severity,
+    };
+
+    /// Parses a source consisting of a single tag
+    fn single_tag(source: &str) -> (Tag, Vec<PaxParseError>) {
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        let Ok([TemplateEntry::Tag(tag)]) = <[_; 1]>::try_from(ast.templates) else {
+            panic!("expected a single tag in: {}", source);
+        };
+        (tag, errors)
+    }
+
+    /// The values of the attributes of a source consisting of a single tag,
+    /// which must all be fields (key=value)
+    fn attribute_values(source: &str) -> (Vec<Expression>, Vec<PaxParseError>) {
+        let (tag, errors) = single_tag(source);
+        let values = tag
+            .attributes
+            .into_iter()
+            .map(|attribute| match attribute {
+                Attribute::Field(field) => field.value,
+                _ => panic!("expected a field, found: {:#?}", attribute),
+            })
+            .collect();
+        (values, errors)
+    }
 
     /// Large test that runs all examples
     /// in the pax_files directory
@@ -135,4 +365,1043 @@ mod tests {
         }
         panic!("one or more pax example files failed to parse, run test with -- --nocapture")
     }
+
+    #[test]
+    fn test_recovers_from_multiple_errors() {
+        let source = r#"
+<Group>
+    <Rectangle fill={5 + } width=10px/>
+    <Text text="hi" 5 />
+</Group>
+@settings {
+    .a { x: 5 + }
+    #b { y: 10px }
+}"#;
+        let mut parser = Parser::new(source);
+        let (ast, errors) = parser.pax_recovering();
+        assert_eq!(errors.len(), 3);
+        assert!(parser.context_stack.is_empty());
+
+        let [TemplateEntry::Tag(group)] = ast.templates.as_slice() else {
+            panic!("expected a single tag, found: {:#?}", ast.templates);
+        };
+        let [TemplateEntry::Tag(rect), TemplateEntry::Tag(text)] = group.body.as_slice() else {
+            panic!("expected two tags, found: {:#?}", group.body);
+        };
+        assert!(matches!(
+            rect.attributes.as_slice(),
+            [Attribute::Field(fill), Attribute::Field(_)] if matches!(fill.value, Expression::Error(_))
+        ));
+        assert_eq!(text.attributes.len(), 1);
+        assert!(matches!(
+            ast.settings.as_slice(),
+            [SettingsEntry::Class(_), SettingsEntry::Id(_)]
+        ));
+    }
+
+    #[test]
+    fn test_inserts_error_nodes() {
+        let source = "<Group> <5 /> <Text/> </Group> } @settings { foo }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert_eq!(errors.len(), 3);
+
+        assert!(matches!(
+            ast.templates.as_slice(),
+            [TemplateEntry::Tag(group), TemplateEntry::Error(_)]
+                if matches!(group.body.as_slice(), [TemplateEntry::Error(_), TemplateEntry::Tag(_)])
+        ));
+        assert!(matches!(ast.settings.as_slice(), [SettingsEntry::Error(_)]));
+    }
+
+    #[test]
+    fn test_eof_span_after_multibyte_character() {
+        let source = "<Rect a=1 /> <G // é";
+        let span = Parser::new(source).eof_span();
+        assert_eq!((span.start, span.end), (source.len(), source.len()));
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let source = "<Group>\n  <Rect x={-a + 1} fill=rgb(1, 2, 3) />\n</Group>\n@settings { .c { y: 5px } }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty());
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        let [TemplateEntry::Tag(group)] = ast.templates.as_slice() else {
+            panic!("expected a single tag, found: {:#?}", ast.templates);
+        };
+        assert_eq!(src(group.span), &source[..source.find("\n@").unwrap()]);
+        assert_eq!(src(group.open_tag), "<Group>");
+        assert_eq!(group.close_tag.map(src), Some("</Group>"));
+        assert_eq!(
+            group.body_span().map(src),
+            Some("\n  <Rect x={-a + 1} fill=rgb(1, 2, 3) />\n")
+        );
+
+        let [TemplateEntry::Tag(rect)] = group.body.as_slice() else {
+            panic!("expected a single tag, found: {:#?}", group.body);
+        };
+        assert_eq!(src(rect.span), "<Rect x={-a + 1} fill=rgb(1, 2, 3) />");
+        assert_eq!(rect.close_tag, None);
+        let [Attribute::Field(x), Attribute::Field(fill)] = rect.attributes.as_slice() else {
+            panic!("expected two fields, found: {:#?}", rect.attributes);
+        };
+        assert_eq!(src(x.span), "x={-a + 1}");
+        let Expression::Binary { left, .. } = &x.value else {
+            panic!("expected binary expression, found: {:#?}", x.value);
+        };
+        assert_eq!(src(x.value.span()), "-a + 1");
+        assert_eq!(src(left.span()), "-a");
+        assert_eq!(src(fill.value.span()), "rgb(1, 2, 3)");
+
+        let [SettingsEntry::Class(class)] = ast.settings.as_slice() else {
+            panic!("expected a single class, found: {:#?}", ast.settings);
+        };
+        assert_eq!(src(class.span), ".c { y: 5px }");
+        assert_eq!(src(class.binding[0].span()), "y: 5px");
+
+        let source = "<Group> slot(i + 1) </Group>";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty());
+        let [TemplateEntry::Tag(group)] = ast.templates.as_slice() else {
+            panic!("expected a single tag, found: {:#?}", ast.templates);
+        };
+        let [TemplateEntry::Slot(slot)] = group.body.as_slice() else {
+            panic!("expected a single slot, found: {:#?}", group.body);
+        };
+        assert_eq!(&source[slot.span.as_range()], "slot(i + 1)");
+        assert_eq!(&source[slot.source.span().as_range()], "i + 1");
+    }
+
+    #[test]
+    fn test_comments() {
+        let source = "/* block /* nested */ comment */\n/// A group\n///of things\n<Group/>\n//// not docs\n/// dangling\n@settings {\n  /// the id\n  #id { x: 1 }\n  /// the class\n  .c { y: 2 }\n}";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        let [TemplateEntry::Comment(block), TemplateEntry::Tag(group), TemplateEntry::Comment(not_docs), TemplateEntry::Comment(dangling)] =
+            ast.templates.as_slice()
+        else {
+            panic!("expected comments and a tag, found: {:#?}", ast.templates);
+        };
+        assert_eq!(src(block.0), "/* block /* nested */ comment */");
+        let docs: Vec<_> = group.docs.iter().map(|d| d.doc_text(source)).collect();
+        assert_eq!(docs, ["A group", "of things"]);
+        assert_eq!(src(group.span), "<Group/>");
+        assert_eq!(src(not_docs.0), "//// not docs");
+        assert_eq!(dangling.doc_text(source), "dangling");
+
+        let [SettingsEntry::Id(id), SettingsEntry::Class(class)] = ast.settings.as_slice() else {
+            panic!("expected an id and a class, found: {:#?}", ast.settings);
+        };
+        assert_eq!(id.docs[0].doc_text(source), "the id");
+        assert_eq!(class.docs[0].doc_text(source), "the class");
+        assert_eq!(src(class.span), ".c { y: 2 }");
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        let source = r#"<Text a="x\q" b={1.2.3} c=5 $ d="\u{1F600}" e="open />"#;
+        let (_, errors) = Parser::new(source).pax_recovering();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ErrorKind::InvalidEscape, r"\q"),
+                (ErrorKind::MalformedNumber, "1.2.3"),
+                (ErrorKind::UnknownCharacter, "$"),
+                (ErrorKind::UnterminatedString, "\""),
+                (ErrorKind::UnexpectedToken, ""),
+            ]
+        );
+
+        // an unterminated block comment would otherwise swallow the rest of the file
+        let source = "<Rect a=1 /> /* oops /* nested */";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(matches!(
+            ast.templates[..],
+            [TemplateEntry::Tag(_), TemplateEntry::Comment(_)]
+        ));
+        let [error] = errors.as_slice() else {
+            panic!("expected a single error, found: {:#?}", errors);
+        };
+        assert_eq!(error.kind(), ErrorKind::UnterminatedComment);
+        assert_eq!(&source[error.span().unwrap().as_range()], "/*");
+    }
+
+    #[test]
+    fn test_string_values() {
+        let source = r###"<Text a="tab\tquote\"" b=r"C:\path\" c=r#"say "hi""# d=r##"a"#b"## />"###;
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let strings: Vec<_> = values
+            .iter()
+            .map(|value| match value {
+                Expression::Value {
+                    value: Value::String(string),
+                    ..
+                } => (string.value.as_str(), &source[string.content.as_range()]),
+                _ => panic!("expected a string, found: {:#?}", value),
+            })
+            .collect();
+        assert_eq!(
+            strings,
+            [
+                ("tab\tquote\"", r#"tab\tquote\""#),
+                (r"C:\path\", r"C:\path\"),
+                (r#"say "hi""#, r#"say "hi""#),
+                (r##"a"#b"##, r##"a"#b"##),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interpolated_strings() {
+        let source =
+            r#"<Text a="Score: {score + 1}{{max}}\n" b="{{plain}}" c="{x" d="}" e="{1 +}" />"#;
+        let (values, errors) = attribute_values(source);
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        let Expression::Value {
+            value: Value::InterpolatedString(score),
+            ..
+        } = &values[0]
+        else {
+            panic!("expected an interpolated string, found: {:#?}", values[0]);
+        };
+        let [StringSegment::Literal { value: before, .. }, StringSegment::Expression(expression), StringSegment::Literal { value: after, span }] =
+            score.segments.as_slice()
+        else {
+            panic!("expected three segments, found: {:#?}", score.segments);
+        };
+        assert_eq!(before, "Score: ");
+        assert_eq!(src(expression.span()), "score + 1");
+        assert_eq!(after, "{max}\n");
+        assert_eq!(src(*span), r"{{max}}\n");
+
+        let Expression::Value {
+            value: Value::String(plain),
+            ..
+        } = &values[1]
+        else {
+            panic!("expected a plain string, found: {:#?}", values[1]);
+        };
+        assert_eq!(plain.value, "{plain}");
+
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), src(e.span().unwrap())))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ErrorKind::InvalidInterpolation, "{"),
+                (ErrorKind::InvalidInterpolation, "}"),
+                (ErrorKind::UnexpectedToken, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strings_in_interpolations() {
+        let source = r#"<Text a="{name == \"bob\"}!" b="{\"}\" + \"{\"}" c="{\"open}" />"#;
+        let (values, errors) = attribute_values(source);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let segments: Vec<_> = values
+            .iter()
+            .map(|value| match value {
+                Expression::Value {
+                    value: Value::InterpolatedString(string),
+                    ..
+                } => &string.segments,
+                _ => panic!("expected an interpolated string, found: {:#?}", value),
+            })
+            .collect();
+
+        let [StringSegment::Expression(Expression::Binary { right, .. }), StringSegment::Literal { .. }] =
+            segments[0].as_slice()
+        else {
+            panic!(
+                "expected a comparison and a literal, found: {:#?}",
+                segments[0]
+            );
+        };
+        let Expression::Value {
+            value: Value::String(bob),
+            ..
+        } = &**right
+        else {
+            panic!("expected a string, found: {:#?}", right);
+        };
+        assert_eq!(bob.value, "bob");
+        assert_eq!(src(bob.span), r#"\"bob\""#);
+
+        // braces in strings don't open or close the embedded expression
+        let [StringSegment::Expression(expression)] = segments[1].as_slice() else {
+            panic!("expected a single expression, found: {:#?}", segments[1]);
+        };
+        assert_eq!(src(expression.span()), r#"\"}\" + \"{\""#);
+
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), src(e.span().unwrap())))
+            .collect();
+        assert_eq!(found, [(ErrorKind::InvalidInterpolation, "{")]);
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let source = "<Rect a=0xFF b=0b1010 c=1_000 d=1e-3 e=.5 f=2.5E2 g={0..5} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let numbers: Vec<_> = values[..6]
+            .iter()
+            .map(|value| match value {
+                Expression::Value { value, .. } => (value.as_i64(), value.as_f64()),
+                _ => panic!("expected a value, found: {:#?}", value),
+            })
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                (Some(255), Some(255.0)),
+                (Some(10), Some(10.0)),
+                (Some(1000), Some(1000.0)),
+                (None, Some(1e-3)),
+                (None, Some(0.5)),
+                (None, Some(250.0)),
+            ]
+        );
+
+        let source = "<Rect a=0x b=0b12 c=99999999999999999999 d=1e999 />";
+        let (_, errors) = Parser::new(source).pax_recovering();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ErrorKind::MalformedNumber, "0x"),
+                (ErrorKind::MalformedNumber, "2"),
+                (ErrorKind::NumberOutOfRange, "99999999999999999999"),
+                (ErrorKind::NumberOutOfRange, "1e999"),
+            ]
+        );
+
+        // i64::MIN only fits when negated
+        let source =
+            "<Rect a={-9223372036854775808} b={-0x8000_0000_0000_0000} c={-9223372036854775809} />";
+        let (values, errors) = attribute_values(source);
+        for value in &values[..2] {
+            let Expression::Value { value, span } = value else {
+                panic!("expected a value, found: {:#?}", value);
+            };
+            assert_eq!(value.as_i64(), Some(i64::MIN));
+            assert!(source[span.as_range()].starts_with('-'));
+        }
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(
+            found,
+            [(ErrorKind::NumberOutOfRange, "9223372036854775809")]
+        );
+    }
+
+    #[test]
+    fn test_color_literals() {
+        let source = "<Rect a=#f04 b=#00ff4480 c={#1e3fff} d=#0b2233 />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let settings = "@settings { #id { fill: #FF0044 } }";
+        let (ast, errors) = Parser::new(settings).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let [SettingsEntry::Id(id)] = ast.settings.as_slice() else {
+            panic!("expected a single id, found: {:#?}", ast.settings);
+        };
+        let FieldOrComment::Field(fill) = &id.binding[0] else {
+            panic!("expected a field, found: {:#?}", id.binding);
+        };
+        let colors: Vec<_> = values
+            .iter()
+            .map(|value| (source, value))
+            .chain([(settings, &fill.value)])
+            .map(|(source, value)| match value {
+                Expression::Value {
+                    value: Value::Color(c),
+                    ..
+                } => ((c.r, c.g, c.b, c.a), &source[c.span.as_range()]),
+                _ => panic!("expected a color, found: {:#?}", value),
+            })
+            .collect();
+        assert_eq!(
+            colors,
+            [
+                ((0xff, 0x00, 0x44, 0xff), "#f04"),
+                ((0x00, 0xff, 0x44, 0x80), "#00ff4480"),
+                ((0x1e, 0x3f, 0xff, 0xff), "#1e3fff"),
+                ((0x0b, 0x22, 0x33, 0xff), "#0b2233"),
+                ((0xff, 0x00, 0x44, 0xff), "#FF0044"),
+            ]
+        );
+
+        let source = "<Rect a=#ff04 b=#ggg c=#0x1234 />";
+        let (_, errors) = Parser::new(source).pax_recovering();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ErrorKind::InvalidColor, "#ff04"),
+                (ErrorKind::InvalidColor, "g"),
+                (ErrorKind::InvalidColor, "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_units_as_names() {
+        let source = "<Rect s=5 px=3 style={ s: 3s, em: 2em } />\n\
+            <Rect x={rad * 2deg} y={em + 1} />\n\
+            for s in self.items { <Rect x={self.s} /> }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Tag(properties), TemplateEntry::Tag(variables), TemplateEntry::Loop(for_loop)] =
+            ast.templates.as_slice()
+        else {
+            panic!("expected two tags and a loop, found: {:#?}", ast.templates);
+        };
+
+        let keys: Vec<_> = properties
+            .attributes
+            .iter()
+            .map(|attribute| src(attribute.span()))
+            .collect();
+        assert_eq!(keys, ["s=5", "px=3", "style={ s: 3s, em: 2em }"]);
+
+        let [Attribute::Field(x), Attribute::Field(y)] = variables.attributes.as_slice() else {
+            panic!("expected two fields, found: {:#?}", variables.attributes);
+        };
+        let Expression::Binary { left, right, .. } = &x.value else {
+            panic!("expected a binary expression, found: {:#?}", x.value);
+        };
+        assert!(matches!(
+            **left,
+            Expression::Value {
+                value: Value::Variable(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            **right,
+            Expression::WithUnit {
+                unit: Unit::Degrees,
+                ..
+            }
+        ));
+        assert!(matches!(y.value, Expression::Binary { .. }));
+
+        assert_eq!(src(for_loop.pattern.span()), "s");
+        assert_eq!(for_loop.body.len(), 1);
+    }
+
+    #[test]
+    fn test_units() {
+        let source =
+            "<Rect a=1.5em b=2rem c=50vw d=30vh e=300ms f=1.5s g=0.25turn h={(a + 1)ms} i=5% />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let units: Vec<_> = values
+            .iter()
+            .map(|value| match value {
+                Expression::WithUnit { unit, .. } => *unit,
+                _ => panic!("expected a value with a unit, found: {:#?}", value),
+            })
+            .collect();
+        assert_eq!(
+            units,
+            [
+                Unit::Em,
+                Unit::Rem,
+                Unit::ViewportWidth,
+                Unit::ViewportHeight,
+                Unit::Milliseconds,
+                Unit::Seconds,
+                Unit::Turns,
+                Unit::Milliseconds,
+                Unit::Percent,
+            ]
+        );
+        for (suffix, unit) in Unit::SUFFIXES {
+            assert_eq!(Unit::from_suffix(suffix), Some(unit));
+            assert_eq!(unit.suffix(), suffix);
+        }
+    }
+
+    #[test]
+    fn test_contextual_keywords() {
+        let source = "<Rect in=5 slot=1 bind=2 bind:value=x style={ slot: 1, in: 2 } />\n\
+            for bind in slot { slot(bind) }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Tag(tag), TemplateEntry::Loop(for_loop)] = ast.templates.as_slice()
+        else {
+            panic!("expected a tag and a loop, found: {:#?}", ast.templates);
+        };
+
+        let keys: Vec<_> = tag
+            .attributes
+            .iter()
+            .map(|attribute| match attribute {
+                Attribute::Field(field) => src(field.key.0),
+                Attribute::Binding(binding) => src(binding.key.0),
+                _ => panic!("expected a field or binding, found: {:#?}", attribute),
+            })
+            .collect();
+        assert_eq!(keys, ["in", "slot", "bind", "value", "style"]);
+        assert!(matches!(tag.attributes[3], Attribute::Binding(_)));
+        let Attribute::Field(Field {
+            value:
+                Expression::Value {
+                    value: Value::Object(style),
+                    ..
+                },
+            ..
+        }) = &tag.attributes[4]
+        else {
+            panic!("expected an object, found: {:#?}", tag.attributes[4]);
+        };
+        let fields: Vec<_> = style.fields.iter().map(|f| src(f.span())).collect();
+        assert_eq!(fields, ["slot: 1", "in: 2"]);
+
+        assert_eq!(src(for_loop.pattern.span()), "bind");
+        assert_eq!(src(for_loop.source.span()), "slot");
+        let [TemplateEntry::Slot(slot)] = for_loop.body.as_slice() else {
+            panic!("expected a single slot, found: {:#?}", for_loop.body);
+        };
+        assert_eq!(src(slot.source.span()), "bind");
+    }
+
+    #[test]
+    fn test_receivers() {
+        let source =
+            "<Text text={self.message + message} @click=this.increment bind:value=self.x />";
+        let (tag, errors) = single_tag(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [Attribute::Field(text), Attribute::Handler(handler), Attribute::Binding(binding)] =
+            tag.attributes.as_slice()
+        else {
+            panic!(
+                "expected a field, handler and binding, found: {:#?}",
+                tag.attributes
+            );
+        };
+        let Expression::Binary { left, right, .. } = &text.value else {
+            panic!("expected a binary expression, found: {:#?}", text.value);
+        };
+        let variables: Vec<_> = [left, right]
+            .into_iter()
+            .map(|e| match &**e {
+                Expression::Value {
+                    value: Value::Variable(variable),
+                    ..
+                } => (variable.receiver, src(variable.span)),
+                _ => panic!("expected a variable, found: {:#?}", e),
+            })
+            .collect();
+        let self_span = crate::Span { start: 12, end: 16 };
+        assert_eq!(
+            variables,
+            [
+                (Receiver::SelfRef(self_span), "self.message"),
+                (Receiver::Implicit, "message")
+            ]
+        );
+        assert!(matches!(handler.receiver, Receiver::SelfRef(s) if src(s) == "this"));
+        assert_eq!(src(handler.value.0), "increment");
+        assert!(matches!(binding.receiver, Receiver::SelfRef(s) if src(s) == "self"));
+        assert_eq!(src(binding.value.0), "x");
+
+        // methods are called on the receiver
+        let source = "<Text text={self.add(1, 2)} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let Expression::Value {
+            value: Value::FunctionCall(call),
+            ..
+        } = &values[0]
+        else {
+            panic!("expected a function call, found: {:#?}", values[0]);
+        };
+        assert!(matches!(call.receiver, Receiver::SelfRef(s) if src(s) == "self"));
+        assert_eq!(src(call.name.0), "add");
+        assert_eq!(call.arguments.len(), 2);
+        assert_eq!(src(call.span), "self.add(1, 2)");
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let source = "<Größe _private=1 名前=_ x={über_α2} />";
+        let (tag, errors) = single_tag(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        assert_eq!(src(tag.name.0), "Größe");
+        let keys: Vec<_> = tag
+            .attributes
+            .iter()
+            .map(|a| src(a.span()).split('=').next().unwrap())
+            .collect();
+        assert_eq!(keys, ["_private", "名前", "x"]);
+
+        // the "е" is cyrillic
+        let source = "<R\u{435}ctangle/>";
+        let (_, errors) = Parser::new(source).pax_recovering();
+        let [warning] = errors.as_slice() else {
+            panic!("expected a single warning, found: {:#?}", errors);
+        };
+        assert_eq!(warning.kind(), ErrorKind::ConfusableCharacter);
+        assert_eq!(warning.severity(), Severity::Warning);
+        assert_eq!(warning.suggestions()[0].apply(source), "<Rectangle/>");
+        assert!(Parser::new(source).pax().is_ok());
+        // fully non ASCII identifiers are not flagged
+        assert!(Parser::new("<Прямоугольник/>")
+            .pax_recovering()
+            .1
+            .is_empty());
+    }
+
+    #[test]
+    fn test_bool_and_none_literals() {
+        let source = "<Rect visible=false a=true b=none c=null d={!false && x} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let literals: Vec<_> = values[..4]
+            .iter()
+            .map(|value| match value {
+                Expression::Value { value, .. } => value,
+                _ => panic!("expected a value, found: {:#?}", value),
+            })
+            .collect();
+        assert_eq!(literals[0].as_bool(), Some(false));
+        assert_eq!(literals[1].as_bool(), Some(true));
+        assert!(matches!(literals[2], Value::None(_)));
+        assert!(matches!(literals[3], Value::None(_)));
+        let Expression::Binary { left, .. } = &values[4] else {
+            panic!("expected a binary expression, found: {:#?}", values[4]);
+        };
+        let Expression::Unary { val, .. } = &**left else {
+            panic!("expected a unary expression, found: {:#?}", left);
+        };
+        assert!(matches!(
+            **val,
+            Expression::Value {
+                value: Value::Bool(BoolLiteral { value: false, .. }),
+                ..
+            }
+        ));
+
+        // only literals where a value is expected
+        let source = "<Rect none=5 true=x null={self.none} style={ false: 1 } />";
+        let (tag, errors) = single_tag(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let fields: Vec<_> = tag
+            .attributes
+            .iter()
+            .map(|attribute| match attribute {
+                Attribute::Field(field) => (src(field.key.0), &field.value),
+                _ => panic!("expected a field, found: {:#?}", attribute),
+            })
+            .collect();
+        let keys: Vec<_> = fields.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, ["none", "true", "null", "style"]);
+        assert!(matches!(
+            fields[1].1,
+            Expression::Value {
+                value: Value::Variable(_),
+                ..
+            }
+        ));
+        let Expression::Value {
+            value: Value::Variable(none),
+            ..
+        } = fields[2].1
+        else {
+            panic!("expected a variable, found: {:#?}", fields[2].1);
+        };
+        assert_eq!(src(none.span), "self.none");
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        let source = "<Rect a={x || y ? 0..5 : 1..3} b={c ? 1 : d ? 2 : 3} \
+            fill={if self.hover {RED} else if x {GREEN} else {BLUE}} e={{ k: {c ? 1px : 2px} }} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        // the condition takes the whole "||", and the branches the whole ranges
+        let Expression::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } = &values[0]
+        else {
+            panic!("expected a conditional, found: {:#?}", values[0]);
+        };
+        assert!(matches!(
+            **condition,
+            Expression::Binary {
+                op: BinaryOp::Or,
+                ..
+            }
+        ));
+        assert_eq!(src(then_branch.span()), "0..5");
+        assert_eq!(src(else_branch.span()), "1..3");
+
+        // right associative
+        let Expression::Conditional { else_branch, .. } = &values[1] else {
+            panic!("expected a conditional, found: {:#?}", values[1]);
+        };
+        assert!(matches!(**else_branch, Expression::Conditional { .. }));
+        assert_eq!(src(else_branch.span()), "d ? 2 : 3");
+
+        // else if chains nest in the else branch
+        let Expression::Conditional {
+            condition,
+            else_branch,
+            span,
+            ..
+        } = &values[2]
+        else {
+            panic!("expected a conditional, found: {:#?}", values[2]);
+        };
+        assert_eq!(src(condition.span()), "self.hover");
+        assert_eq!(src(else_branch.span()), "if x {GREEN} else {BLUE}");
+        assert_eq!(
+            src(*span),
+            "if self.hover {RED} else if x {GREEN} else {BLUE}"
+        );
+
+        let Expression::Value {
+            value: Value::Object(object),
+            ..
+        } = &values[3]
+        else {
+            panic!("expected an object, found: {:#?}", values[3]);
+        };
+        let [FieldOrComment::Field(field)] = object.fields.as_slice() else {
+            panic!("expected a single field, found: {:#?}", object.fields);
+        };
+        assert!(matches!(field.value, Expression::Conditional { .. }));
+        assert_eq!(src(field.value.span()), "c ? 1px : 2px");
+
+        // else is required
+        let (_, errors) = Parser::new("<Rect a={if c {1}} />").pax_recovering();
+        assert!(!errors.is_empty());
+
+        // else is only a keyword after the then branch
+        let source = "<Rect else=1 x={else + 1} y={if a {else} else {2}} />";
+        let (tag, errors) = single_tag(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let keys: Vec<_> = tag
+            .attributes
+            .iter()
+            .map(|attribute| match attribute {
+                Attribute::Field(field) => src(field.key.0),
+                _ => panic!("expected a field, found: {:#?}", attribute),
+            })
+            .collect();
+        assert_eq!(keys, ["else", "x", "y"]);
+        let Attribute::Field(y) = &tag.attributes[2] else {
+            panic!("expected a field, found: {:#?}", tag.attributes[2]);
+        };
+        let Expression::Conditional { then_branch, .. } = &y.value else {
+            panic!("expected a conditional, found: {:#?}", y.value);
+        };
+        assert_eq!(src(then_branch.span()), "else");
+    }
+
+    #[test]
+    fn test_postfix_access() {
+        let source = "<Rect a={self.items[0].name} b={self.list.len()} c={get_pos(i).x * 2} \
+            d={-(a + b).values[i + 1]px} e={self.a.b} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        let Expression::FieldAccess { val, field, .. } = &values[0] else {
+            panic!("expected a field access, found: {:#?}", values[0]);
+        };
+        assert_eq!(src(field.0), "name");
+        let Expression::Index { val, index, .. } = &**val else {
+            panic!("expected an index, found: {:#?}", val);
+        };
+        assert_eq!(src(index.span()), "0");
+        let Expression::Value {
+            value: Value::Variable(variable),
+            ..
+        } = &**val
+        else {
+            panic!("expected a variable, found: {:#?}", val);
+        };
+        assert_eq!(src(variable.span), "self.items");
+
+        // the method isn't part of the variable path
+        let Expression::MethodCall {
+            val,
+            method,
+            arguments,
+            ..
+        } = &values[1]
+        else {
+            panic!("expected a method call, found: {:#?}", values[1]);
+        };
+        assert_eq!(src(val.span()), "self.list");
+        assert_eq!(src(method.0), "len");
+        assert!(arguments.is_empty());
+
+        let Expression::Binary { left, .. } = &values[2] else {
+            panic!("expected a binary expression, found: {:#?}", values[2]);
+        };
+        let Expression::FieldAccess { val, .. } = &**left else {
+            panic!("expected a field access, found: {:#?}", left);
+        };
+        assert!(matches!(
+            **val,
+            Expression::Value {
+                value: Value::FunctionCall(_),
+                ..
+            }
+        ));
+
+        // accesses bind tighter than units, which bind tighter than prefix operators
+        let Expression::Unary { val, .. } = &values[3] else {
+            panic!("expected a unary expression, found: {:#?}", values[3]);
+        };
+        let Expression::WithUnit { val, .. } = &**val else {
+            panic!("expected a unit, found: {:#?}", val);
+        };
+        assert_eq!(src(val.span()), "(a + b).values[i + 1]");
+
+        assert!(matches!(
+            values[4],
+            Expression::Value {
+                value: Value::Variable(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_closures() {
+        let source = "<List sort_by={|a, b| a.x < b.x} map={|x| x * 2} make={|| 5} \
+            any={a || b} nested={|x| |y| x + y} />";
+        let (values, errors) = attribute_values(source);
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+
+        let Expression::Closure { params, body, .. } = &values[0] else {
+            panic!("expected a closure, found: {:#?}", values[0]);
+        };
+        let params: Vec<_> = params.iter().map(|p| src(p.0)).collect();
+        assert_eq!(params, ["a", "b"]);
+        assert!(matches!(
+            **body,
+            Expression::Binary {
+                op: BinaryOp::SmallerThan,
+                ..
+            }
+        ));
+        assert_eq!(src(body.span()), "a.x < b.x");
+
+        let Expression::Closure { body, .. } = &values[1] else {
+            panic!("expected a closure, found: {:#?}", values[1]);
+        };
+        assert_eq!(src(body.span()), "x * 2");
+
+        let Expression::Closure { params, .. } = &values[2] else {
+            panic!("expected a closure, found: {:#?}", values[2]);
+        };
+        assert!(params.is_empty());
+
+        // "||" between two operands is still "or"
+        assert!(matches!(
+            values[3],
+            Expression::Binary {
+                op: BinaryOp::Or,
+                ..
+            }
+        ));
+
+        let Expression::Closure { body, .. } = &values[4] else {
+            panic!("expected a closure, found: {:#?}", values[4]);
+        };
+        assert!(matches!(**body, Expression::Closure { .. }));
+
+        let (_, errors) = Parser::new("<List map={|x y| x} />").pax_recovering();
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn test_let_bindings() {
+        let source = "for i in 0..10 {
+    let x = (i % 3) * self.cell_size;
+    <Rectangle x={x} y={x * 2} />
+}
+<Text let=5 />
+let y = 2";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Loop(for_loop), TemplateEntry::Tag(text), TemplateEntry::Error(_)] =
+            ast.templates.as_slice()
+        else {
+            panic!(
+                "expected a loop, a tag and an error, found: {:#?}",
+                ast.templates
+            );
+        };
+        let [TemplateEntry::Let(binding), TemplateEntry::Tag(_)] = for_loop.body.as_slice() else {
+            panic!(
+                "expected a let binding and a tag, found: {:#?}",
+                for_loop.body
+            );
+        };
+        assert_eq!(src(binding.name.0), "x");
+        assert_eq!(src(binding.value.span()), "(i % 3) * self.cell_size");
+        assert_eq!(src(binding.span), "let x = (i % 3) * self.cell_size;");
+
+        // let is still allowed as an attribute name
+        assert_eq!(text.attributes.len(), 1);
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(errors, [(ErrorKind::UnexpectedToken, "")]);
+    }
+
+    #[test]
+    fn test_else_branches() {
+        let source = "if a { <A/> } else if b { <B/> } else if c {} else { <D/> <E/> }
+if x { <X/> }
+if y {} else {}";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Conditional(chain), TemplateEntry::Conditional(single), TemplateEntry::Conditional(empty)] =
+            ast.templates.as_slice()
+        else {
+            panic!("expected three conditionals, found: {:#?}", ast.templates);
+        };
+
+        let keywords: Vec<_> = chain.branches.iter().map(|b| src(b.keyword)).collect();
+        assert_eq!(keywords, ["if", "else if", "else if"]);
+        let conditions: Vec<_> = chain
+            .branches
+            .iter()
+            .map(|b| src(b.condition.span()))
+            .collect();
+        assert_eq!(conditions, ["a", "b", "c"]);
+        assert_eq!(src(chain.branches[1].span), "else if b { <B/> }");
+        assert!(chain.branches[2].body.is_empty());
+        let Some(fallback) = &chain.fallback else {
+            panic!("expected an else branch, found: {:#?}", chain);
+        };
+        assert_eq!(src(fallback.keyword), "else");
+        assert_eq!(fallback.body.len(), 2);
+        assert_eq!(
+            src(chain.span),
+            "if a { <A/> } else if b { <B/> } else if c {} else { <D/> <E/> }"
+        );
+
+        assert_eq!(single.branches.len(), 1);
+        assert!(single.fallback.is_none());
+        assert_eq!(src(single.span), "if x { <X/> }");
+
+        assert!(empty.fallback.is_some());
+
+        let (_, errors) = Parser::new("if a {} else <A/>").pax_recovering();
+        assert!(!errors.is_empty());
+
+        // else is still a name inside the branches
+        let source = "if a {} else { <A else={if b {1} else {2}} /> }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Conditional(conditional)] = ast.templates.as_slice() else {
+            panic!("expected a single conditional, found: {:#?}", ast.templates);
+        };
+        let Some(fallback) = &conditional.fallback else {
+            panic!("expected an else branch, found: {:#?}", conditional);
+        };
+        let [TemplateEntry::Tag(tag)] = fallback.body.as_slice() else {
+            panic!("expected a single tag, found: {:#?}", fallback.body);
+        };
+        assert_eq!(src(tag.span), "<A else={if b {1} else {2}} />");
+    }
+
+    #[test]
+    fn test_match() {
+        let source = "match self.game_state {
+    GameState::Playing(score, lives) => { <Text text={score} /> }
+    Paused => {},
+    _ => { <Text text=\"GAME OVER\" /> }
+}
+match state { Over(self.x) => {} }";
+        let (ast, errors) = Parser::new(source).pax_recovering();
+        let src = |span: crate::Span| &source[span.as_range()];
+        let [TemplateEntry::Match(game), TemplateEntry::Match(invalid)] = ast.templates.as_slice()
+        else {
+            panic!("expected two matches, found: {:#?}", ast.templates);
+        };
+        assert_eq!(src(game.value.span()), "self.game_state");
+        let [playing, paused, wildcard] = game.arms.as_slice() else {
+            panic!("expected three arms, found: {:#?}", game.arms);
+        };
+
+        let ArmPattern::Variant(pattern) = &playing.pattern else {
+            panic!("expected a variant, found: {:#?}", playing.pattern);
+        };
+        assert_eq!(
+            pattern.name.as_ref().map(|name| src(name.0)),
+            Some("GameState")
+        );
+        assert_eq!(src(pattern.variant.0), "Playing");
+        let bindings: Vec<_> = pattern.bindings.iter().map(|b| src(b.0)).collect();
+        assert_eq!(bindings, ["score", "lives"]);
+        assert_eq!(playing.body.len(), 1);
+
+        let ArmPattern::Variant(pattern) = &paused.pattern else {
+            panic!("expected a variant, found: {:#?}", paused.pattern);
+        };
+        assert!(pattern.name.is_none());
+        assert!(pattern.bindings.is_empty());
+        assert_eq!(src(paused.span), "Paused => {}");
+
+        assert!(matches!(wildcard.pattern, ArmPattern::Wildcard(_)));
+        assert_eq!(wildcard.body.len(), 1);
+
+        // match is contextual and state {..} isn't mistaken for an object
+        assert_eq!(src(invalid.value.span()), "state");
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.kind(), &source[e.span().unwrap().as_range()]))
+            .collect();
+        assert_eq!(errors, [(ErrorKind::InvalidPattern, "self.x")]);
+    }
 }
@@ -3,7 +3,7 @@ use pax_parser_macros::token_context;
 use crate::{
     ast::{
         common::{Comment, Field, FieldOrComment, Identifier},
-        expression::Object,
+        expression::{Expression, Object},
     },
     lexer::TokenKind,
 };
@@ -29,7 +29,10 @@ impl<'src> Parser<'src> {
         // skip an unknown ammount of legal comment blocks.
         // this isn't great since it's technically an arbitrary ammount
         // of lookahead, but  should very very seldom be more than 3 or 4 or so
-        while self.peek_nth_token(lookahead) == TokenKind::Comment {
+        while matches!(
+            self.peek_nth_token(lookahead),
+            TokenKind::Comment | TokenKind::DocComment
+        ) {
             lookahead += 1;
         }
         // expect an identifier
@@ -42,16 +45,21 @@ impl<'src> Parser<'src> {
             return false;
         }
         // now we are sure, this must be a map
-        return true;
+        true
     }
 
     #[token_context("Object (<optional ident> {foo: .. bar: ..})")]
     pub fn object(&mut self) -> Result<Object, PaxParseError> {
+        let start = self.peek_span().start;
         let name = self
             .next_token_if(|t| t == TokenKind::Identifier)
             .map(|t| Identifier(t.span));
         let fields = self.map()?;
-        Ok(Object { name, fields })
+        Ok(Object {
+            name,
+            fields,
+            span: self.span_from(start),
+        })
     }
 
     #[token_context("Map ({foo: .. bar: ..})")]
@@ -59,26 +67,53 @@ impl<'src> Parser<'src> {
         self.expect(TokenKind::OpenCurlBrack)?;
         let mut entries = vec![];
         loop {
-            entries.push(match self.peek_token() {
-                TokenKind::Identifier => {
-                    let key = self.next_token();
-                    self.expect(TokenKind::Colon)?;
-                    let value = self.literal_or_wrapped_expression()?;
-                    //skip comma between fields
-                    self.next_token_if(|t| t == TokenKind::Comma);
-                    FieldOrComment::Field(Field {
-                        key: Identifier(key.span),
-                        value,
-                    })
+            let start = self.peek_span().start;
+            let entry = match self.peek_token() {
+                TokenKind::Identifier => self.field().map(FieldOrComment::Field),
+                TokenKind::Comment | TokenKind::DocComment => {
+                    Ok(FieldOrComment::Comment(Comment(self.next_token().span)))
                 }
-                TokenKind::Comment => FieldOrComment::Comment(Comment(self.next_token().span)),
                 TokenKind::CloseCurlBrack => {
-                    self.tokens.next();
+                    self.next_token();
                     break;
                 }
-                _ => return Err(self.error([TokenKind::Identifier])),
-            });
+                TokenKind::EOF => return Err(self.error([TokenKind::CloseCurlBrack])),
+                _ => Err(self.error([TokenKind::Identifier])),
+            };
+            match entry {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    self.recover(e, start, Self::is_field_boundary);
+                }
+            }
         }
         Ok(entries)
     }
+
+    fn field(&mut self) -> Result<Field, PaxParseError> {
+        let [key, _] = self.expect_sequence([TokenKind::Identifier, TokenKind::Colon])?;
+        let start = self.peek_span().start;
+        let value = self
+            .literal_or_wrapped_expression()
+            .unwrap_or_else(|e| Expression::Error(self.recover(e, start, Self::is_field_boundary)));
+        let span = self.span_from(key.span.start);
+        //skip comma between fields
+        self.next_token_if(|t| t == TokenKind::Comma);
+        Ok(Field {
+            key: Identifier(key.span),
+            value,
+            span,
+        })
+    }
+
+    fn is_field_boundary(&mut self) -> bool {
+        matches!(
+            self.peek_token(),
+            TokenKind::Comma
+                | TokenKind::Comment
+                | TokenKind::DocComment
+                | TokenKind::CloseCurlBrack
+        ) || (self.peek_token() == TokenKind::Identifier
+            && self.peek_nth_token(1) == TokenKind::Colon)
+    }
 }
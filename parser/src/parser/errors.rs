@@ -1,19 +1,32 @@
 use codespan_reporting::diagnostic::{Diagnostic, Label};
-use codespan_reporting::files::SimpleFiles;
 use codespan_reporting::term;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
-use std::fmt::Write;
+use codespan_reporting::term::termcolor::{Ansi, NoColor};
+use std::error::Error;
+use std::fmt::{Display, Write};
+use std::io::IsTerminal;
 
 use crate::{
     lexer::{Span, Token, TokenKind},
+    source_map::{FileId, SourceMap},
     Parser,
 };
 
+mod codes;
+mod json;
+mod sarif;
+pub use codes::{explain, ErrorKind, Severity};
+pub use sarif::{to_sarif, to_sarif_with_source_map};
+
 impl<'src> Parser<'src> {
     pub fn expect(&mut self, token: TokenKind) -> Result<Token, PaxParseError> {
         let next = self.next_token();
-        if next.kind == token {
-            Ok(next)
+        let is_keyword = next.kind == TokenKind::Identifier
+            && token.contextual_keyword() == Some(self.source_of(next.span));
+        if next.kind == token || is_keyword {
+            Ok(Token {
+                kind: token,
+                ..next
+            })
         } else {
             Err(self.expected_with_span(next.span, [token]))
         }
@@ -35,12 +48,11 @@ impl<'src> Parser<'src> {
         self.expected_with_span(tok.span, expected_tokens)
     }
 
-    fn expected_with_span<const N: usize>(
+    pub fn expected_with_span<const N: usize>(
         &self,
         span: Span,
         tokens: [TokenKind; N],
     ) -> PaxParseError {
-        // TODO add info from self.context
         let mut expect_str = String::from("expected ");
         match tokens.len() {
             0 => {
@@ -60,83 +72,494 @@ impl<'src> Parser<'src> {
                 write!(expect_str, " or {}", tokens[last]).unwrap();
             }
         }
-        PaxParseError::new("unexpected character(s)").annotation(span, expect_str)
+
+        // innermost contexts are the most relevant ones, only show a few of them
+        let contexts: Vec<_> = self
+            .context_stack
+            .iter()
+            .rev()
+            .take(MAX_CONTEXT_DEPTH)
+            .collect();
+        let description = match contexts.split_first() {
+            Some(((innermost, _), outer)) => {
+                let mut description = format!("while parsing {}", innermost);
+                for (context, _) in outer {
+                    write!(description, " inside {}", context).unwrap();
+                }
+                write!(description, ": {}", expect_str).unwrap();
+                description
+            }
+            None => format!("unexpected character(s): {}", expect_str),
+        };
+
+        let mut error = PaxParseError::new(ErrorKind::UnexpectedToken, description)
+            .annotation(span, expect_str);
+        let mut annotated = vec![span];
+        for &&(context, context_span) in &contexts {
+            // only annotate the innermost context starting at each location
+            if !annotated.contains(&context_span) {
+                annotated.push(context_span);
+                error = error.annotation(context_span, format!("{} starts here", context));
+            }
+        }
+        error
     }
 }
 
+/// Number of enclosing contexts (from the innermost and out)
+/// mentioned when reporting an unexpected token
+const MAX_CONTEXT_DEPTH: usize = 3;
+
 #[derive(Debug)]
 pub struct Annotation {
+    /// None if in the file being parsed, see PaxParseError::in_file
+    file: Option<FileId>,
     span: Span,
     text: String,
     annotation_type: AnnotationType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AnnotationType {
     Primary,
     Secondary,
 }
 
+/// A machine applicable fix: replacing the source at `span`
+/// with `replacement` (an empty span means insertion).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub file: Option<FileId>,
+    pub span: Span,
+    pub replacement: String,
+    pub message: String,
+}
+
+impl Suggestion {
+    /// Returns the source with this suggestion applied
+    pub fn apply(&self, source: &str) -> String {
+        let mut fixed = source.to_owned();
+        fixed.replace_range(self.span.as_range(), &self.replacement);
+        fixed
+    }
+}
+
 #[derive(Debug)]
 pub struct PaxParseError {
-    _error_code: usize,
+    kind: ErrorKind,
     short_description: String,
     annotations: Vec<Annotation>,
     help: Option<String>,
+    suggestions: Vec<Suggestion>,
 }
 
 impl PaxParseError {
-    pub fn new(short_description: impl Into<String>) -> Self {
+    pub fn new(kind: ErrorKind, short_description: impl Into<String>) -> Self {
         Self {
-            // TODO fill in error codes
-            _error_code: 0,
+            kind,
             short_description: short_description.into(),
             annotations: Vec::new(),
             help: None,
+            suggestions: Vec::new(),
         }
     }
 
-    pub fn annotation(mut self, span: Span, text: impl Into<String>) -> Self {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+
+    pub fn help_text(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+    pub fn help(mut self, text: impl Into<String>) -> Self {
+        self.help = Some(text.into());
+        self
+    }
+
+    pub fn suggestion(
+        self,
+        span: Span,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.suggestion_with_file(None, span, replacement, message)
+    }
+
+    /// Suggestion replacing a span in another file than the one being parsed
+    pub fn suggestion_in_file(
+        self,
+        file: FileId,
+        span: Span,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.suggestion_with_file(Some(file), span, replacement, message)
+    }
+
+    fn suggestion_with_file(
+        mut self,
+        file: Option<FileId>,
+        span: Span,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.suggestions.push(Suggestion {
+            file,
+            span,
+            replacement: replacement.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn annotation(self, span: Span, text: impl Into<String>) -> Self {
+        self.annotation_with_file(None, span, text)
+    }
+
+    /// Annotation pointing into another file than the one being parsed
+    pub fn annotation_in_file(self, file: FileId, span: Span, text: impl Into<String>) -> Self {
+        self.annotation_with_file(Some(file), span, text)
+    }
+
+    fn annotation_with_file(
+        mut self,
+        file: Option<FileId>,
+        span: Span,
+        text: impl Into<String>,
+    ) -> Self {
         self.annotations.push(Annotation {
             annotation_type: match self.annotations.is_empty() {
                 true => AnnotationType::Primary,
                 false => AnnotationType::Secondary,
             },
+            file,
             span,
             text: text.into(),
         });
         self
     }
 
+    /// Assigns all annotations and suggestions not yet
+    /// associated with a file to the given file
+    pub fn in_file(mut self, file: FileId) -> Self {
+        for a in &mut self.annotations {
+            a.file.get_or_insert(file);
+        }
+        for s in &mut self.suggestions {
+            s.file.get_or_insert(file);
+        }
+        self
+    }
+
+    /// Checks if any annotation or suggestion isn't assigned to a file
+    fn has_missing_file(&self) -> bool {
+        self.annotations.iter().any(|a| a.file.is_none())
+            || self.suggestions.iter().any(|s| s.file.is_none())
+    }
+
+    /// The span of the primary annotation
+    pub fn span(&self) -> Option<Span> {
+        self.annotations.first().map(|a| a.span)
+    }
+
+    /// The file of the primary annotation
+    pub fn file(&self) -> Option<FileId> {
+        self.annotations.first().and_then(|a| a.file)
+    }
+
+    /// Prints the error to stderr, with colors if stderr is a terminal.
+    /// All annotations are shown in the given file.
     pub fn print_with_file(
         &self,
         file_name: &str,
         file: &str,
     ) -> Result<(), codespan_reporting::files::Error> {
-        let mut files = SimpleFiles::new();
-        let file_id = files.add(file_name, file);
-        let diagnostic = Diagnostic::error()
+        let (files, file_id) = single_file(file_name, file);
+        self.print(&files, Some(file_id))
+    }
+
+    /// Renders the error into a string, with source snippets from file.
+    /// All annotations are shown in the given file.
+    pub fn render_with_file(
+        &self,
+        file_name: &str,
+        file: &str,
+        color: ColorMode,
+    ) -> Result<String, codespan_reporting::files::Error> {
+        let (files, file_id) = single_file(file_name, file);
+        self.render(&files, Some(file_id), color)
+    }
+
+    /// Writes the error to any writer, with source snippets from file.
+    /// All annotations are shown in the given file. ColorMode::Auto never
+    /// emits colors here since the writer can't be inspected, use Always to force them.
+    pub fn write_with_file(
+        &self,
+        writer: &mut dyn std::io::Write,
+        file_name: &str,
+        file: &str,
+        color: ColorMode,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        let (files, file_id) = single_file(file_name, file);
+        self.write(writer, &files, Some(file_id), color)
+    }
+
+    /// Like print_with_file, but annotations can point into any file in the source map
+    pub fn print_with_source_map(
+        &self,
+        files: &SourceMap,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        self.print(files, None)
+    }
+
+    /// Like render_with_file, but annotations can point into any file in the source map
+    pub fn render_with_source_map(
+        &self,
+        files: &SourceMap,
+        color: ColorMode,
+    ) -> Result<String, codespan_reporting::files::Error> {
+        self.render(files, None, color)
+    }
+
+    /// Like write_with_file, but annotations can point into any file in the source map
+    pub fn write_with_source_map(
+        &self,
+        writer: &mut dyn std::io::Write,
+        files: &SourceMap,
+        color: ColorMode,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        self.write(writer, files, None, color)
+    }
+
+    // file_override places all annotations in that file, instead of the one they reference
+    fn print(
+        &self,
+        files: &SourceMap,
+        file_override: Option<FileId>,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        let stderr = std::io::stderr();
+        let color = match stderr.is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+            true => ColorMode::Always,
+            false => ColorMode::Never,
+        };
+        self.write(&mut stderr.lock(), files, file_override, color)
+    }
+
+    fn render(
+        &self,
+        files: &SourceMap,
+        file_override: Option<FileId>,
+        color: ColorMode,
+    ) -> Result<String, codespan_reporting::files::Error> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer, files, file_override, color)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    fn write(
+        &self,
+        writer: &mut dyn std::io::Write,
+        files: &SourceMap,
+        file_override: Option<FileId>,
+        color: ColorMode,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        let diagnostic = self.diagnostic(file_override)?;
+        let config = codespan_reporting::term::Config::default();
+
+        match color {
+            ColorMode::Always => term::emit(&mut Ansi::new(writer), &config, files, &diagnostic),
+            ColorMode::Never | ColorMode::Auto => {
+                term::emit(&mut NoColor::new(writer), &config, files, &diagnostic)
+            }
+        }
+    }
+
+    fn diagnostic(
+        &self,
+        file_override: Option<FileId>,
+    ) -> Result<Diagnostic<FileId>, codespan_reporting::files::Error> {
+        let labels = self
+            .annotations
+            .iter()
+            .map(|a| {
+                let file = file_override
+                    .or(a.file)
+                    .ok_or(codespan_reporting::files::Error::FileMissing)?;
+                Ok(match a.annotation_type {
+                    AnnotationType::Primary => Label::primary(file, a.span).with_message(&a.text),
+                    AnnotationType::Secondary => {
+                        Label::secondary(file, a.span).with_message(&a.text)
+                    }
+                })
+            })
+            .collect::<Result<_, codespan_reporting::files::Error>>()?;
+        let diagnostic = match self.severity() {
+            Severity::Error => Diagnostic::error(),
+            Severity::Warning => Diagnostic::warning(),
+        };
+        Ok(diagnostic
             .with_message(&self.short_description)
-            .with_code("E")
-            .with_labels(
-                self.annotations
+            .with_code(self.kind.code())
+            .with_labels(labels)
+            .with_notes(
+                self.help
                     .iter()
-                    .map(|a| match a.annotation_type {
-                        AnnotationType::Primary => {
-                            Label::primary(file_id, a.span).with_message(&a.text)
-                        }
-                        AnnotationType::Secondary => {
-                            Label::secondary(file_id, a.span).with_message(&a.text)
-                        }
-                    })
+                    .map(|help| format!("help: {}", help))
+                    .chain(
+                        self.suggestions
+                            .iter()
+                            .map(|s| format!("suggestion: {}", s.message)),
+                    )
                     .collect(),
-            )
-            .with_notes(self.help.as_slice().to_vec());
+            ))
+    }
+}
 
-        let writer = StandardStream::stderr(ColorChoice::Always);
-        let config = codespan_reporting::term::Config::default();
+fn single_file(file_name: &str, file: &str) -> (SourceMap, FileId) {
+    let mut files = SourceMap::new();
+    let file_id = files.add(file_name, file);
+    (files, file_id)
+}
+
+impl Display for PaxParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.kind.code(), self.short_description)
+    }
+}
+
+impl Error for PaxParseError {}
+
+/// Whether to use colors when rendering errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    Always,
+    Never,
+    /// Colors when printing to a terminal, unless the NO_COLOR environment variable is set
+    #[default]
+    Auto,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnotationType, ColorMode, ErrorKind};
+    use crate::Parser;
+
+    #[test]
+    fn test_error_mentions_contexts() {
+        let source = "<Group>\n    for 5 in items {}\n</Group>";
+        let err = Parser::new(source).pax().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+        assert_eq!(
+            err.short_description,
+            "while parsing For loop (for i in items {..}) inside Template \
+            inside Tag pair (<tag>..</tag>): expected identifier"
+        );
+
+        let annotations: Vec<_> = err
+            .annotations
+            .iter()
+            .map(|a| (&source[a.span.as_range()], a.annotation_type))
+            .collect();
+        assert_eq!(
+            annotations,
+            [
+                ("5", AnnotationType::Primary),
+                ("for", AnnotationType::Secondary),
+                ("<", AnnotationType::Secondary),
+            ]
+        );
+    }
+
+    /// Parses source expecting a single error, and checks that
+    /// applying its suggestion results in the expected fixed source
+    fn assert_fixed_by_suggestion(source: &str, fixed: &str) {
+        let (_, errors) = Parser::new(source).pax_recovering();
+        let [err] = errors.as_slice() else {
+            panic!("expected a single error, found: {:#?}", errors);
+        };
+        let [suggestion] = err.suggestions() else {
+            panic!(
+                "expected a single suggestion, found: {:#?}",
+                err.suggestions()
+            );
+        };
+        assert_eq!(suggestion.apply(source), fixed);
+        let (_, errors) = Parser::new(fixed).pax_recovering();
+        assert!(errors.is_empty(), "fixed source has errors: {:#?}", errors);
+    }
+
+    #[test]
+    fn test_mismatched_closing_tag_suggestion() {
+        assert_fixed_by_suggestion("<Group><Rectangle/></Grup>", "<Group><Rectangle/></Group>");
+    }
+
+    #[test]
+    fn test_missing_assign_suggestion() {
+        assert_fixed_by_suggestion("<Text text\"hello\"/>", "<Text text=\"hello\"/>");
+        assert_fixed_by_suggestion("<Rectangle x{5 + 3}/>", "<Rectangle x={5 + 3}/>");
+    }
+
+    #[test]
+    fn test_handler_separator_suggestion() {
+        assert_fixed_by_suggestion("<Rectangle @click:foo/>", "<Rectangle @click=foo/>");
+        assert_fixed_by_suggestion("@settings { @mount=foo }", "@settings { @mount:foo }");
+    }
+
+    #[test]
+    fn test_missing_self_closing_slash_suggestion() {
+        assert_fixed_by_suggestion(
+            "<Group><Rectangle x=5><Text/></Group>",
+            "<Group><Rectangle x=5/><Text/></Group>",
+        );
+    }
+
+    #[test]
+    fn test_render_to_string() {
+        let source = "<Group></Grup>";
+        let err = Parser::new(source).pax().unwrap_err();
+        assert_eq!(err.to_string(), "[P0002] unexpected closing tag");
+
+        let plain = err
+            .render_with_file("test.pax", source, ColorMode::Never)
+            .unwrap();
+        assert!(plain.starts_with("error[P0002]: unexpected closing tag"));
+        assert!(plain.contains("test.pax:1:10"));
+        assert!(!plain.contains('\u{1b}'));
+
+        let colored = err
+            .render_with_file("test.pax", source, ColorMode::Always)
+            .unwrap();
+        assert!(colored.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let source = "<Group>\n</Grup>";
+        let err = Parser::new(source).pax().unwrap_err();
+        let json = err.to_json("test.pax", source);
+        assert!(json.starts_with(r#"{"code":"P0002","message":"unexpected closing tag","severity":"error","file":"test.pax","range":{"byte_start":10,"byte_end":14,"start":{"line":2,"column":3,"utf16_column":3},"end":{"line":2,"column":7,"utf16_column":7}}"#));
+        assert!(json.contains(r#""replacement":"Group""#));
+    }
 
-        term::emit(&mut writer.lock(), &config, &files, &diagnostic)?;
-        Ok(())
+    #[test]
+    fn test_sarif_output() {
+        let source = "<Group>\n</Grup>";
+        let err = Parser::new(source).pax().unwrap_err();
+        let sarif = super::to_sarif(&[err], "test.pax", source);
+        assert!(sarif.contains(r#""version":"2.1.0""#));
+        assert!(sarif.contains(r#""ruleId":"P0002""#));
+        assert!(
+            sarif.contains(r#""region":{"startLine":2,"startColumn":3,"endLine":2,"endColumn":7"#)
+        );
+        assert!(sarif.contains(r#""insertedContent":{"text":"Group"}"#));
     }
 }
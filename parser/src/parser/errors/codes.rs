@@ -0,0 +1,348 @@
+/// The kind of a [`super::PaxParseError`], each with a stable error code
+/// that can be looked up with [`explain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedToken,
+    MismatchedClosingTag,
+    NonSettingsBlock,
+    UnterminatedString,
+    UnknownCharacter,
+    InvalidEscape,
+    MalformedNumber,
+    UnterminatedComment,
+    InvalidInterpolation,
+    NumberOutOfRange,
+    InvalidColor,
+    ConfusableCharacter,
+    InvalidPattern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Doesn't prevent the source from being parsed, see Parser::pax
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 13] = [
+        ErrorKind::UnexpectedToken,
+        ErrorKind::MismatchedClosingTag,
+        ErrorKind::NonSettingsBlock,
+        ErrorKind::UnterminatedString,
+        ErrorKind::UnknownCharacter,
+        ErrorKind::InvalidEscape,
+        ErrorKind::MalformedNumber,
+        ErrorKind::UnterminatedComment,
+        ErrorKind::InvalidInterpolation,
+        ErrorKind::NumberOutOfRange,
+        ErrorKind::InvalidColor,
+        ErrorKind::ConfusableCharacter,
+        ErrorKind::InvalidPattern,
+    ];
+
+    /// Stable error code, never reuse or renumber these
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken => "P0001",
+            ErrorKind::MismatchedClosingTag => "P0002",
+            ErrorKind::NonSettingsBlock => "P0003",
+            ErrorKind::UnterminatedString => "P0004",
+            ErrorKind::UnknownCharacter => "P0005",
+            ErrorKind::InvalidEscape => "P0006",
+            ErrorKind::MalformedNumber => "P0007",
+            ErrorKind::UnterminatedComment => "P0008",
+            ErrorKind::InvalidInterpolation => "P0009",
+            ErrorKind::NumberOutOfRange => "P0010",
+            ErrorKind::InvalidColor => "P0011",
+            ErrorKind::ConfusableCharacter => "P0012",
+            ErrorKind::InvalidPattern => "P0013",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            ErrorKind::ConfusableCharacter => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<ErrorKind> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Long form description of the error, with examples
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken => {
+                r#"A token was found where it isn't allowed by the pax grammar.
+
+Erroneous code example:
+
+```
+<Rectangle width= />
+```
+
+An attribute needs a value after "=". The error lists the tokens
+that would have been valid at this position, and which constructs
+(tags, loops, expressions, ..) were being parsed when it was found:
+
+```
+<Rectangle width=100px />
+```
+"#
+            }
+            ErrorKind::MismatchedClosingTag => {
+                r#"A closing tag doesn't match the most recently opened tag.
+
+Erroneous code example:
+
+```
+<Group>
+    <Rectangle />
+</Grup>
+```
+
+Tags must be closed in the reverse order they were opened,
+using the same name as the opening tag:
+
+```
+<Group>
+    <Rectangle />
+</Group>
+```
+"#
+            }
+            ErrorKind::NonSettingsBlock => {
+                r#"A block other than @settings was found at the top level.
+
+Erroneous code example:
+
+```
+@style {
+    .centered { x: 50% }
+}
+```
+
+The only block allowed next to the template is @settings,
+which contains handlers, classes and ids:
+
+```
+@settings {
+    .centered { x: 50% }
+}
+```
+"#
+            }
+            ErrorKind::UnterminatedString => {
+                r#"A string literal is missing its closing quote.
+
+Erroneous code example:
+
+```
+<Text text="hello />
+```
+
+Strings start and end with a double quote ("), make sure the
+closing quote is present and not escaped with a backslash:
+
+```
+<Text text="hello" />
+```
+"#
+            }
+            ErrorKind::UnknownCharacter => {
+                r#"A character that isn't part of the pax syntax was found.
+
+Erroneous code example:
+
+```
+<Rectangle width={5 $ 3} />
+```
+
+Remove the character, or if it was meant as part of a string,
+wrap it in double quotes:
+
+```
+<Rectangle width={5 * 3} />
+```
+"#
+            }
+            ErrorKind::InvalidEscape => {
+                r#"A string contains a backslash that doesn't start a valid escape sequence.
+
+Erroneous code example:
+
+```
+<Text text="C:\Users\pax" />
+```
+
+The valid escapes are \n, \r, \t, \0, \\, \", \' and unicode
+escapes such as \u{1F600}. To include a backslash, escape it:
+
+```
+<Text text="C:\\Users\\pax" />
+```
+"#
+            }
+            ErrorKind::MalformedNumber => {
+                r#"A number literal isn't well formed.
+
+Erroneous code example:
+
+```
+<Rectangle width={1.5.0} />
+```
+
+A number can contain at most one decimal point:
+
+```
+<Rectangle width={1.5} />
+```
+"#
+            }
+            ErrorKind::UnterminatedComment => {
+                r#"A block comment is missing its closing "*/".
+
+Erroneous code example:
+
+```
+/* a comment /* with a nested comment */
+<Rectangle />
+```
+
+Block comments can be nested, so each "/*" needs its own "*/".
+Without it, the rest of the file is part of the comment:
+
+```
+/* a comment /* with a nested comment */ */
+<Rectangle />
+```
+"#
+            }
+            ErrorKind::InvalidInterpolation => {
+                r#"A string contains a brace that doesn't open or close an embedded expression.
+
+Erroneous code example:
+
+```
+<Text text="Score: {self.score" />
+```
+
+Expressions embedded in strings are enclosed in braces. To include
+a literal brace in a string, write it twice ({{ or }}):
+
+```
+<Text text="Score: {self.score} {{max 100}}" />
+```
+"#
+            }
+            ErrorKind::NumberOutOfRange => {
+                r#"A number literal is too large to be represented.
+
+Erroneous code example:
+
+```
+<Rectangle width={99999999999999999999px} />
+```
+
+Integers are stored as 64 bit signed integers (at most 9223372036854775807),
+and floats as 64 bit floats. Use a smaller number, or a float instead of an
+integer if precision isn't important:
+
+```
+<Rectangle width={1e20px} />
+```
+"#
+            }
+            ErrorKind::InvalidColor => {
+                r#"A hex color literal doesn't have a valid number of hex digits.
+
+Erroneous code example:
+
+```
+<Rectangle fill=#ff04 />
+```
+
+Colors are written as #rgb, #rrggbb or #rrggbbaa, where each
+letter is a hexadecimal digit (0-9, a-f):
+
+```
+<Rectangle fill=#ff0044 />
+```
+"#
+            }
+            ErrorKind::ConfusableCharacter => {
+                r#"An identifier contains a letter that looks like an ASCII letter, but isn't one.
+
+Erroneous code example (the "е" is the Cyrillic letter U+0435):
+
+```
+<Rеctangle />
+```
+
+Such identifiers look the same as the intended name but refer to something
+else, which is usually a mistake (or an attempt to hide one). This is a
+warning, the source is still parsed. Replace the letter with its ASCII
+counterpart:
+
+```
+<Rectangle />
+```
+"#
+            }
+            ErrorKind::InvalidPattern => {
+                r#"A match arm binds a variant's payload to something other than a name.
+
+Erroneous code example:
+
+```
+match self.state {
+    State::Playing(self.score) => { <Text text={score} /> }
+}
+```
+
+The values in a variant's parentheses are bound to new names, which are
+in scope in the body of the arm. Use a plain name for each of them:
+
+```
+match self.state {
+    State::Playing(score) => { <Text text={score} /> }
+}
+```
+"#
+            }
+        }
+    }
+}
+
+/// Long form description of the error with the given code (such as "P0001")
+pub fn explain(code: &str) -> Option<&'static str> {
+    ErrorKind::from_code(code).map(ErrorKind::explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, ErrorKind};
+
+    #[test]
+    fn test_codes_are_unique_and_explained() {
+        for (i, kind) in ErrorKind::ALL.into_iter().enumerate() {
+            assert_eq!(ErrorKind::from_code(kind.code()), Some(kind));
+            assert!(ErrorKind::ALL[..i].iter().all(|k| k.code() != kind.code()));
+            assert!(explain(kind.code()).is_some());
+        }
+        assert_eq!(explain("P9999"), None);
+    }
+}
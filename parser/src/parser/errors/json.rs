@@ -0,0 +1,175 @@
+use std::fmt::{self, Display, Write};
+
+use crate::{
+    lexer::Span,
+    source_map::{FileId, SourceMap},
+};
+
+use super::{AnnotationType, PaxParseError};
+
+/// Minimal JSON value, only what is needed to serialize diagnostics
+#[derive(Debug, Clone)]
+pub enum Json {
+    Null,
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}:{}", Json::from(*key), v)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn position(files: &SourceMap, file: FileId, offset: usize) -> Json {
+    let location = files.line_column(file, offset);
+    Json::Object(vec![
+        ("line", (location.line + 1).into()),
+        ("column", (location.column + 1).into()),
+        ("utf16_column", (location.utf16_column + 1).into()),
+    ])
+}
+
+fn range(files: &SourceMap, file: FileId, span: Span) -> Json {
+    Json::Object(vec![
+        ("byte_start", span.start.into()),
+        ("byte_end", span.end.into()),
+        ("start", position(files, file, span.start)),
+        ("end", position(files, file, span.end)),
+    ])
+}
+
+impl PaxParseError {
+    /// Serializes the error into a single line JSON object,
+    /// all annotations and suggestions are assumed to be in the given file
+    pub fn to_json(&self, file_name: &str, file: &str) -> String {
+        let (files, file_id) = super::single_file(file_name, file);
+        self.json_value(&files, Some(file_id)).to_string()
+    }
+
+    /// Serializes the error into a single line JSON object,
+    /// with annotations and suggestions resolved to the files they point into
+    pub fn to_json_with_source_map(
+        &self,
+        files: &SourceMap,
+    ) -> Result<String, codespan_reporting::files::Error> {
+        if self.has_missing_file() {
+            return Err(codespan_reporting::files::Error::FileMissing);
+        }
+        Ok(self.json_value(files, None).to_string())
+    }
+
+    // file_override places all annotations and suggestions in that file, instead of the one they reference
+    fn json_value(&self, files: &SourceMap, file_override: Option<FileId>) -> Json {
+        let file_of = |file: Option<FileId>| {
+            file_override
+                .or(file)
+                .expect("checked that all annotations and suggestions have a file")
+        };
+        let labels = self
+            .annotations
+            .iter()
+            .map(|a| {
+                let file = file_of(a.file);
+                Json::Object(vec![
+                    (
+                        "style",
+                        match a.annotation_type {
+                            AnnotationType::Primary => "primary",
+                            AnnotationType::Secondary => "secondary",
+                        }
+                        .into(),
+                    ),
+                    ("message", a.text.as_str().into()),
+                    ("file", files.name(file).into()),
+                    ("range", range(files, file, a.span)),
+                ])
+            })
+            .collect();
+        let suggestions = self
+            .suggestions
+            .iter()
+            .map(|s| {
+                let file = file_of(s.file);
+                Json::Object(vec![
+                    ("message", s.message.as_str().into()),
+                    ("replacement", s.replacement.as_str().into()),
+                    ("file", files.name(file).into()),
+                    ("range", range(files, file, s.span)),
+                ])
+            })
+            .collect();
+        let primary = self.annotations.first().map(|a| (file_of(a.file), a.span));
+        Json::Object(vec![
+            ("code", self.kind.code().into()),
+            ("message", self.short_description.as_str().into()),
+            ("severity", self.kind.severity().as_str().into()),
+            ("file", primary.map(|(file, _)| files.name(file)).into()),
+            (
+                "range",
+                primary.map_or(Json::Null, |(file, span)| range(files, file, span)),
+            ),
+            ("labels", Json::Array(labels)),
+            ("help", self.help.as_deref().into()),
+            ("suggestions", Json::Array(suggestions)),
+        ])
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    lexer::Span,
+    source_map::{FileId, SourceMap},
+};
+
+use super::{json::Json, single_file, ErrorKind, PaxParseError};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Serializes errors found in a file into a SARIF 2.1.0 log,
+/// all annotations and suggestions are assumed to be in the given file
+pub fn to_sarif(errors: &[PaxParseError], file_name: &str, file: &str) -> String {
+    let (files, file_id) = single_file(file_name, file);
+    sarif_log(errors, &files, Some(file_id))
+}
+
+/// Serializes errors found in any number of files into a SARIF 2.1.0 log,
+/// with annotations and suggestions resolved to the files they point into
+pub fn to_sarif_with_source_map(
+    errors: &[PaxParseError],
+    files: &SourceMap,
+) -> Result<String, codespan_reporting::files::Error> {
+    if errors.iter().any(|e| e.has_missing_file()) {
+        return Err(codespan_reporting::files::Error::FileMissing);
+    }
+    Ok(sarif_log(errors, files, None))
+}
+
+// file_override places all annotations and suggestions in that file, instead of the one they reference
+fn sarif_log(errors: &[PaxParseError], files: &SourceMap, file_override: Option<FileId>) -> String {
+    let rules = ErrorKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let explanation = kind.explanation();
+            let summary = explanation.lines().next().unwrap_or_default();
+            Json::Object(vec![
+                ("id", kind.code().into()),
+                ("name", format!("{:?}", kind).as_str().into()),
+                ("shortDescription", text(summary)),
+                ("fullDescription", text(explanation)),
+            ])
+        })
+        .collect();
+    let results = errors
+        .iter()
+        .map(|e| sarif_result(e, files, file_override))
+        .collect();
+    let driver = Json::Object(vec![
+        ("name", env!("CARGO_PKG_NAME").into()),
+        ("version", env!("CARGO_PKG_VERSION").into()),
+        ("rules", Json::Array(rules)),
+    ]);
+    let run = Json::Object(vec![
+        ("tool", Json::Object(vec![("driver", driver)])),
+        ("columnKind", "unicodeCodePoints".into()),
+        ("results", Json::Array(results)),
+    ]);
+    Json::Object(vec![
+        ("$schema", SARIF_SCHEMA.into()),
+        ("version", "2.1.0".into()),
+        ("runs", Json::Array(vec![run])),
+    ])
+    .to_string()
+}
+
+fn sarif_result(error: &PaxParseError, files: &SourceMap, file_override: Option<FileId>) -> Json {
+    let file_of = |file: Option<FileId>| {
+        file_override
+            .or(file)
+            .expect("checked that all annotations and suggestions have a file")
+    };
+    let mut annotations = error.annotations.iter();
+    let locations = annotations
+        .next()
+        .map(|a| location(files, file_of(a.file), a.span, None))
+        .into_iter()
+        .collect();
+    let related = annotations
+        .map(|a| location(files, file_of(a.file), a.span, Some(&a.text)))
+        .collect();
+    let fixes = error
+        .suggestions
+        .iter()
+        .map(|s| {
+            let file = file_of(s.file);
+            let replacement = Json::Object(vec![
+                ("deletedRegion", region(files, file, s.span)),
+                ("insertedContent", text(&s.replacement)),
+            ]);
+            let change = Json::Object(vec![
+                ("artifactLocation", artifact(files, file)),
+                ("replacements", Json::Array(vec![replacement])),
+            ]);
+            Json::Object(vec![
+                ("description", text(&s.message)),
+                ("artifactChanges", Json::Array(vec![change])),
+            ])
+        })
+        .collect();
+
+    let mut message = error.short_description.clone();
+    if let Some(help) = &error.help {
+        message.push_str("\nhelp: ");
+        message.push_str(help);
+    }
+    Json::Object(vec![
+        ("ruleId", error.kind.code().into()),
+        ("level", error.kind.severity().as_str().into()),
+        ("message", text(&message)),
+        ("locations", Json::Array(locations)),
+        ("relatedLocations", Json::Array(related)),
+        ("fixes", Json::Array(fixes)),
+    ])
+}
+
+fn text(text: &str) -> Json {
+    Json::Object(vec![("text", text.into())])
+}
+
+fn artifact(files: &SourceMap, file: FileId) -> Json {
+    Json::Object(vec![("uri", files.name(file).into())])
+}
+
+fn region(files: &SourceMap, file: FileId, span: Span) -> Json {
+    let (start, end) = files.span_location(file, span);
+    Json::Object(vec![
+        ("startLine", (start.line + 1).into()),
+        ("startColumn", (start.column + 1).into()),
+        ("endLine", (end.line + 1).into()),
+        ("endColumn", (end.column + 1).into()),
+        ("byteOffset", span.start.into()),
+        ("byteLength", (span.end - span.start).into()),
+    ])
+}
+
+fn location(files: &SourceMap, file: FileId, span: Span, message: Option<&str>) -> Json {
+    let physical = Json::Object(vec![
+        ("artifactLocation", artifact(files, file)),
+        ("region", region(files, file, span)),
+    ]);
+    let mut fields = vec![("physicalLocation", physical)];
+    if let Some(message) = message {
+        fields.push(("message", text(message)));
+    }
+    Json::Object(fields)
+}
@@ -1,7 +1,10 @@
 use pax_parser_macros::token_context;
 
 use crate::{
-    ast::expression::{BinaryOp, Expression, Op, UnaryOp, Unit},
+    ast::{
+        common::Identifier,
+        expression::{BinaryOp, Expression, Op, UnaryOp, Value},
+    },
     lexer::TokenKind,
 };
 
@@ -23,12 +26,17 @@ impl<'src> Parser<'src> {
         } else {
             // parse iteral, and convert it into expression form
             let lit = self.literal()?;
+            let value = Expression::Value {
+                value: lit.value,
+                span: lit.value_span,
+            };
             match lit.unit {
                 Some(unit) => Expression::WithUnit {
-                    val: Box::new(Expression::Value(lit.value)),
+                    val: Box::new(value),
                     unit,
+                    span: lit.span,
                 },
-                None => Expression::Value(lit.value),
+                None => value,
             }
         })
     }
@@ -38,43 +46,63 @@ impl<'src> Parser<'src> {
     }
 
     fn expression_with_min_bp(&mut self, min_bp: u8) -> Result<Expression, PaxParseError> {
+        let start = self.peek_span().start;
         let mut value = match self.peek_token() {
             TokenKind::Identifier
             | TokenKind::Integer
             | TokenKind::Float
+            | TokenKind::Hashtag
             | TokenKind::OpenCurlBrack
             | TokenKind::String
             | TokenKind::OpenSquareBrack
-            | TokenKind::OpenParenth => Expression::Value(self.value()?),
+            | TokenKind::OpenParenth => Expression::Value {
+                value: self.value()?,
+                span: self.span_from(start),
+            },
+            TokenKind::If => self.if_expression()?,
+            TokenKind::VertLine | TokenKind::Or => self.closure()?,
             TokenKind::Not => {
                 let ((), rbp) = prefix_binding_power(UnaryOp::Not);
-                self.tokens.next();
+                self.next_token();
                 let rhs = self.expression_with_min_bp(rbp)?;
                 Expression::Unary {
                     op: UnaryOp::Not,
                     val: Box::new(rhs),
+                    span: self.span_from(start),
                 }
             }
-            TokenKind::Minus => {
-                //prefix minus
-                let ((), rbp) = prefix_binding_power(UnaryOp::Neg);
-                self.tokens.next();
-                let rhs = self.expression_with_min_bp(rbp)?;
-                Expression::Unary {
-                    op: UnaryOp::Neg,
-                    val: Box::new(rhs),
+            TokenKind::Minus => match self.min_int() {
+                Some(int) => Expression::Value {
+                    span: int.span,
+                    value: Value::Int(int),
+                },
+                None => {
+                    //prefix minus
+                    let ((), rbp) = prefix_binding_power(UnaryOp::Neg);
+                    self.next_token();
+                    let rhs = self.expression_with_min_bp(rbp)?;
+                    Expression::Unary {
+                        op: UnaryOp::Neg,
+                        val: Box::new(rhs),
+                        span: self.span_from(start),
+                    }
                 }
-            }
-            a => {
-                eprintln!("found: {:?}", a);
+            },
+            _ => {
                 return Err(self.error([
                     TokenKind::Identifier,
                     TokenKind::Integer,
                     TokenKind::Float,
+                    TokenKind::Hashtag,
+                    TokenKind::True,
+                    TokenKind::False,
+                    TokenKind::None,
                     TokenKind::OpenCurlBrack,
                     TokenKind::String,
                     TokenKind::OpenSquareBrack,
                     TokenKind::OpenParenth,
+                    TokenKind::If,
+                    TokenKind::VertLine,
                     TokenKind::Not,
                     TokenKind::Minus,
                 ]));
@@ -84,10 +112,10 @@ impl<'src> Parser<'src> {
         loop {
             let op = match self.peek_token() {
                 // units
-                TokenKind::Pixels => Op::Postfix(Unit::Pixels),
-                TokenKind::Percent => Op::Postfix(Unit::Percent),
-                TokenKind::Degrees => Op::Postfix(Unit::Degrees),
-                TokenKind::Radians => Op::Postfix(Unit::Radians),
+                TokenKind::Percent | TokenKind::Identifier => match self.peek_unit() {
+                    Some(unit) => Op::Postfix(unit),
+                    None => break,
+                },
 
                 // binary operators
                 TokenKind::Plus => Op::Binary(BinaryOp::Add),
@@ -104,6 +132,9 @@ impl<'src> Parser<'src> {
                 TokenKind::CloseAngBrack => Op::Binary(BinaryOp::LargerThan),
                 TokenKind::OpenAngBrack => Op::Binary(BinaryOp::SmallerThan),
                 TokenKind::Exp => Op::Binary(BinaryOp::Exp),
+                TokenKind::Question => Op::Conditional,
+                TokenKind::Period if self.peek_nth_token(1) == TokenKind::Identifier => Op::Member,
+                TokenKind::OpenSquareBrack => Op::Index,
                 TokenKind::Slash => {
                     if self.peek_nth_token(1) == TokenKind::CloseAngBrack {
                         // this slash is part of a closing tag not a
@@ -124,58 +155,183 @@ impl<'src> Parser<'src> {
                         break;
                     }
                     //consume operator
-                    self.tokens.next();
+                    self.next_token();
                     let rhs = self.expression_with_min_bp(rbp)?;
 
                     value = Expression::Binary {
                         left: Box::new(value),
                         op,
                         right: Box::new(rhs),
+                        span: self.span_from(start),
+                    };
+                }
+                Op::Conditional => {
+                    let (lbp, rbp) = conditional_binding_power();
+                    if lbp < min_bp {
+                        break;
+                    }
+                    //consume "?"
+                    self.next_token();
+                    let then_branch = self.expression_with_min_bp(0)?;
+                    self.expect(TokenKind::Colon)?;
+                    let else_branch = self.expression_with_min_bp(rbp)?;
+                    value = Expression::Conditional {
+                        condition: Box::new(value),
+                        then_branch: Box::new(then_branch),
+                        else_branch: Box::new(else_branch),
+                        span: self.span_from(start),
+                    };
+                }
+                Op::Member => {
+                    let lbp = access_binding_power();
+                    if lbp < min_bp {
+                        break;
+                    }
+                    let [_, name] =
+                        self.expect_sequence([TokenKind::Period, TokenKind::Identifier])?;
+                    value = if self.peek_token() == TokenKind::OpenParenth {
+                        let arguments = self.sequence_enclosed_in(
+                            TokenKind::OpenParenth,
+                            TokenKind::CloseParenth,
+                        )?;
+                        Expression::MethodCall {
+                            val: Box::new(value),
+                            method: Identifier(name.span),
+                            arguments,
+                            span: self.span_from(start),
+                        }
+                    } else {
+                        Expression::FieldAccess {
+                            val: Box::new(value),
+                            field: Identifier(name.span),
+                            span: self.span_from(start),
+                        }
+                    };
+                }
+                Op::Index => {
+                    let lbp = access_binding_power();
+                    if lbp < min_bp {
+                        break;
+                    }
+                    self.expect(TokenKind::OpenSquareBrack)?;
+                    let index = self.expression()?;
+                    self.expect(TokenKind::CloseSquareBrack)?;
+                    value = Expression::Index {
+                        val: Box::new(value),
+                        index: Box::new(index),
+                        span: self.span_from(start),
                     };
                 }
                 Op::Postfix(unit) => {
-                    let (lbp, ()) = postfix_binding_power(unit);
+                    let (lbp, ()) = postfix_binding_power();
                     if lbp < min_bp {
                         break;
                     }
                     //consume operator
-                    self.tokens.next();
+                    self.next_token();
                     value = Expression::WithUnit {
                         val: Box::new(value),
                         unit,
+                        span: self.span_from(start),
                     };
                 }
             }
         }
         Ok(value)
     }
+
+    #[token_context("Conditional expression (if c {a} else {b})")]
+    fn if_expression(&mut self) -> Result<Expression, PaxParseError> {
+        let start = self.expect(TokenKind::If)?.span.start;
+        let condition = self.expression()?;
+        let then_branch = self.braced_expression()?;
+        self.expect(TokenKind::Else)?;
+        // else if chains are nested conditionals
+        let else_branch = match self.peek_token() {
+            TokenKind::If => self.if_expression()?,
+            _ => self.braced_expression()?,
+        };
+        Ok(Expression::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+            span: self.span_from(start),
+        })
+    }
+
+    #[token_context("Closure (|a, b| expr)")]
+    fn closure(&mut self) -> Result<Expression, PaxParseError> {
+        let start = self.peek_span().start;
+        let mut params = vec![];
+        // "||" is lexed as a single token, and in prefix position
+        // it can only be the empty parameter list of a closure
+        if self.next_token_if(|t| t == TokenKind::Or).is_none() {
+            self.expect(TokenKind::VertLine)?;
+            loop {
+                if self.next_token_if(|t| t == TokenKind::VertLine).is_some() {
+                    break;
+                }
+                let param = self.expect(TokenKind::Identifier)?;
+                params.push(Identifier(param.span));
+                if self.next_token_if(|t| t == TokenKind::Comma).is_none() {
+                    self.expect(TokenKind::VertLine)?;
+                    break;
+                }
+            }
+        }
+        // like in rust, the body extends as far to the right as possible
+        let body = self.expression()?;
+        Ok(Expression::Closure {
+            params,
+            body: Box::new(body),
+            span: self.span_from(start),
+        })
+    }
+
+    fn braced_expression(&mut self) -> Result<Expression, PaxParseError> {
+        self.expect(TokenKind::OpenCurlBrack)?;
+        let expression = self.expression()?;
+        self.expect(TokenKind::CloseCurlBrack)?;
+        Ok(expression)
+    }
 }
 
 fn bin_binding_powers(op: &BinaryOp) -> (u8, u8) {
     match op {
-        BinaryOp::Or | BinaryOp::And => (1, 2),
+        BinaryOp::Or | BinaryOp::And => (3, 4),
         BinaryOp::NotEq
         | BinaryOp::LessOrEq
         | BinaryOp::MoreOrEq
         | BinaryOp::LargerThan
         | BinaryOp::SmallerThan
-        | BinaryOp::Eq => (3, 4),
-        BinaryOp::Add | BinaryOp::Sub => (5, 6),
-        BinaryOp::Mult | BinaryOp::Div => (7, 8),
-        BinaryOp::Mod => (9, 10),
-        BinaryOp::Exp => (13, 14),
-        BinaryOp::Range => (15, 16),
+        | BinaryOp::Eq => (5, 6),
+        BinaryOp::Add | BinaryOp::Sub => (7, 8),
+        BinaryOp::Mult | BinaryOp::Div => (9, 10),
+        BinaryOp::Mod => (11, 12),
+        BinaryOp::Exp => (15, 16),
+        BinaryOp::Range => (17, 18),
     }
 }
 
+/// Binds the loosest of all operators, and is right associative:
+/// a || b ? c : d ? e : f is (a || b) ? c : (d ? e : f)
+fn conditional_binding_power() -> (u8, u8) {
+    (1, 1)
+}
+
 fn prefix_binding_power(op: UnaryOp) -> ((), u8) {
     match op {
-        UnaryOp::Neg | UnaryOp::Not => ((), 17),
+        UnaryOp::Neg | UnaryOp::Not => ((), 19),
     }
 }
 
-fn postfix_binding_power(unit: Unit) -> (u8, ()) {
-    match unit {
-        Unit::Pixels | Unit::Degrees | Unit::Radians | Unit::Percent => (19, ()),
-    }
+fn postfix_binding_power() -> (u8, ()) {
+    // all units bind the same
+    (21, ())
+}
+
+/// Field access, method calls and indexing bind tighter than anything else:
+/// -a.b[0]px is -(((a.b)[0])px)
+fn access_binding_power() -> u8 {
+    23
 }
@@ -1,9 +1,6 @@
 use pax_parser_macros::token_context;
 
-use crate::{
-    ast::expression::{Literal, Unit, Value},
-    lexer::TokenKind,
-};
+use crate::ast::expression::{Literal, Value};
 
 use super::{Parser, PaxParseError};
 
@@ -12,26 +9,21 @@ impl<'src> Parser<'src> {
     pub fn literal(&mut self) -> Result<Literal, PaxParseError> {
         // most literals are just the same as values allowed in an expression.
         // The only possible addition is a postfix unit for floats and ints.
+        let start = self.peek_span().start;
         let value = self.value()?;
+        let value_span = self.span_from(start);
 
         let is_numeric = matches!(&value, Value::Int(_) | Value::Float(_));
-        let next_is_unit = matches!(
-            self.peek_token(),
-            TokenKind::Pixels | TokenKind::Percent | TokenKind::Radians | TokenKind::Degrees,
-        );
+        let unit = self.peek_unit().filter(|_| is_numeric);
+        if unit.is_some() {
+            self.next_token();
+        }
 
-        let unit = if is_numeric && next_is_unit {
-            Some(match self.next_token().kind {
-                TokenKind::Pixels => Unit::Pixels,
-                TokenKind::Percent => Unit::Percent,
-                TokenKind::Radians => Unit::Radians,
-                TokenKind::Degrees => Unit::Degrees,
-                _ => unreachable!("already checked with if above"),
-            })
-        } else {
-            None
-        };
-
-        Ok(Literal { value, unit })
+        Ok(Literal {
+            value,
+            unit,
+            value_span,
+            span: self.span_from(start),
+        })
     }
 }
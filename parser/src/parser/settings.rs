@@ -8,7 +8,7 @@ use crate::{
     lexer::TokenKind,
 };
 
-use super::{Parser, PaxParseError};
+use super::{ErrorKind, Parser, PaxParseError};
 
 impl<'src> Parser<'src> {
     #[token_context("Settings")]
@@ -19,71 +19,111 @@ impl<'src> Parser<'src> {
             TokenKind::OpenCurlBrack,
         ])?;
         if self.source_of(ident.span) != "settings" {
-            return Err(PaxParseError::new("expected settings block")
-                .annotation(ident.span, "only settings allowed in top level context"));
+            // still parse the body as settings to be able to report errors inside it
+            let err = PaxParseError::new(ErrorKind::NonSettingsBlock, "expected settings block")
+                .annotation(ident.span, "only settings allowed in top level context");
+            self.errors.push(err);
         }
         let mut entries = vec![];
         loop {
-            entries.push(match self.peek_token() {
-                TokenKind::AtSymbol => SettingsEntry::Handler(self.handler()?),
-                TokenKind::Period => SettingsEntry::Class(self.class()?),
-                TokenKind::Hashtag => SettingsEntry::Id(self.id()?),
-                TokenKind::Comment => {
-                    SettingsEntry::Comment(Comment(self.expect(TokenKind::Comment)?.span))
+            let start = self.peek_span().start;
+            let entry = match self.peek_token() {
+                TokenKind::AtSymbol => self.handler().map(SettingsEntry::Handler),
+                TokenKind::Period => self.class().map(SettingsEntry::Class),
+                TokenKind::Hashtag => self.id().map(SettingsEntry::Id),
+                TokenKind::Comment => Ok(SettingsEntry::Comment(Comment(self.next_token().span))),
+                TokenKind::DocComment => {
+                    let count = self.doc_comment_count();
+                    match self.peek_nth_token(count) {
+                        TokenKind::Period => self.class().map(SettingsEntry::Class),
+                        TokenKind::Hashtag => self.id().map(SettingsEntry::Id),
+                        // not documenting a class or id, keep it as a regular comment
+                        _ => Ok(SettingsEntry::Comment(Comment(self.next_token().span))),
+                    }
                 }
                 TokenKind::CloseCurlBrack => {
-                    self.tokens.next();
+                    self.next_token();
                     break;
                 }
-                _ => {
-                    return Err(self.error([
-                        TokenKind::AtSymbol,
-                        TokenKind::Period,
-                        TokenKind::Hashtag,
-                        TokenKind::Comment,
-                        TokenKind::CloseCurlBrack,
-                    ]));
-                }
-            });
+                TokenKind::EOF => return Err(self.error([TokenKind::CloseCurlBrack])),
+                _ => Err(self.error([
+                    TokenKind::AtSymbol,
+                    TokenKind::Period,
+                    TokenKind::Hashtag,
+                    TokenKind::Comment,
+                    TokenKind::CloseCurlBrack,
+                ])),
+            };
+            entries.push(entry.unwrap_or_else(|e| {
+                SettingsEntry::Error(self.recover(e, start, Self::is_settings_entry_boundary))
+            }));
             //skip commas if they exist
             self.next_token_if(|t| t == TokenKind::Comma);
         }
         Ok(entries)
     }
 
-    #[token_context("Handler (@handler=foo)")]
+    #[token_context("Handler (@handler: foo)")]
     fn handler(&mut self) -> Result<Handler, PaxParseError> {
-        let [_, name, _, ident] = self.expect_sequence([
-            TokenKind::AtSymbol,
-            TokenKind::Identifier,
-            TokenKind::Colon,
-            TokenKind::Identifier,
-        ])?;
+        let [at, name] = self.expect_sequence([TokenKind::AtSymbol, TokenKind::Identifier])?;
+        if self.peek_token() == TokenKind::Assign {
+            // tag syntax used in settings, report it and continue as if it was ":"
+            let assign = self.next_token();
+            let err = self
+                .expected_with_span(assign.span, [TokenKind::Colon])
+                .help("handlers are bound with \":\" in @settings, and with \"=\" in tags")
+                .suggestion(assign.span, ":", "replace \"=\" with \":\"");
+            self.errors.push(err);
+        } else {
+            self.expect(TokenKind::Colon)?;
+        }
+        let receiver = self.receiver();
+        let ident = self.expect(TokenKind::Identifier)?;
         Ok(Handler {
             key: Identifier(name.span),
+            receiver,
             value: Identifier(ident.span),
+            span: self.span_from(at.span.start),
         })
     }
 
     #[token_context("Class (.a_class {..})")]
     fn class(&mut self) -> Result<Class, PaxParseError> {
-        self.expect(TokenKind::Period)?;
+        let docs = self.doc_comments();
+        let period = self.expect(TokenKind::Period)?;
         let name = self.expect(TokenKind::Identifier)?;
         let body = self.map()?;
         Ok(Class {
+            docs,
             name: Identifier(name.span),
             binding: body,
+            span: self.span_from(period.span.start),
         })
     }
 
     #[token_context("Id (#a_class {..})")]
     fn id(&mut self) -> Result<Id, PaxParseError> {
-        self.expect(TokenKind::Hashtag)?;
+        let docs = self.doc_comments();
+        let hashtag = self.expect(TokenKind::Hashtag)?;
         let name = self.expect(TokenKind::Identifier)?;
         let body = self.map()?;
         Ok(Id {
+            docs,
             name: Identifier(name.span),
             binding: body,
+            span: self.span_from(hashtag.span.start),
         })
     }
+
+    fn is_settings_entry_boundary(&mut self) -> bool {
+        matches!(
+            self.peek_token(),
+            TokenKind::AtSymbol
+                | TokenKind::Period
+                | TokenKind::Hashtag
+                | TokenKind::Comment
+                | TokenKind::DocComment
+                | TokenKind::CloseCurlBrack
+        )
+    }
 }
@@ -0,0 +1,197 @@
+use std::{iter::Peekable, str::CharIndices};
+
+use pax_parser_macros::token_context;
+
+use crate::{
+    ast::expression::{Expression, InterpolatedString, StringLiteral, StringSegment, Value},
+    lexer::{unescape, Span, TokenKind},
+};
+
+use super::{ErrorKind, Parser, PaxParseError};
+
+impl<'src> Parser<'src> {
+    /// Parses a string literal, which is interpolated if it contains
+    /// expressions in braces ("Score: {self.score}"). Literal braces are written {{ and }}.
+    #[token_context("String (\"..\")")]
+    pub fn string(&mut self) -> Value {
+        let span = self.next_token().span;
+        let text = self.source_of(span);
+        let raw = text.starts_with('r');
+        // strings in expressions embedded in strings are delimited by \"
+        let escaped = text.starts_with('\\');
+        let hashes = match raw {
+            true => text[1..].len() - text[1..].trim_start_matches('#').len(),
+            false => 0,
+        };
+        // delimiters are "r#.." + '"' before, and '"' + "#.." after
+        let open = match (raw, escaped) {
+            (true, _) => 2 + hashes,
+            (false, true) => 2,
+            (false, false) => 1,
+        };
+        let closing = match escaped {
+            true => "\\\"".to_owned(),
+            false => "\"".to_owned() + &"#".repeat(hashes),
+        };
+        // unterminated strings (already reported by the lexer) run to the end of the file
+        let close = match text.len() >= open + closing.len() && text.ends_with(&closing) {
+            true => closing.len(),
+            false => 0,
+        };
+        let content = Span {
+            start: span.start + open,
+            end: (span.end - close).max(span.start + open),
+        };
+        // strings inside embedded expressions aren't interpolated themselves
+        if raw || escaped {
+            let value = match raw {
+                true => self.source_of(content).to_owned(),
+                false => unescape(self.source_of(content)),
+            };
+            return Value::String(StringLiteral {
+                content,
+                value,
+                raw,
+                span,
+            });
+        }
+
+        let (value, segments) = self.string_segments(content);
+        match segments.is_empty() {
+            true => Value::String(StringLiteral {
+                content,
+                value,
+                raw,
+                span,
+            }),
+            false => Value::InterpolatedString(InterpolatedString { segments, span }),
+        }
+    }
+
+    /// Splits the content of a string into literals and embedded expressions. If there are
+    /// no embedded expressions, the segments are empty and the decoded content is returned.
+    fn string_segments(&mut self, content: Span) -> (String, Vec<StringSegment>) {
+        let src = self.source_of(content);
+        let mut segments = vec![];
+        let mut value = String::new();
+        // start of the part of the current literal that isn't yet decoded into value
+        let mut pending = 0;
+        let mut literal_start = 0;
+        let mut chars = src.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    // skip the escaped character, or a whole unicode escape
+                    let escaped = chars.next();
+                    if escaped.is_some_and(|(_, c)| c == 'u')
+                        && chars.peek().is_some_and(|&(_, c)| c == '{')
+                    {
+                        while chars.next().is_some_and(|(_, c)| c != '}') {}
+                    }
+                }
+                '{' | '}' if chars.next_if(|&(_, next)| next == c).is_some() => {
+                    value += &unescape(&src[pending..i]);
+                    value.push(c);
+                    pending = i + 2;
+                }
+                '{' => {
+                    let close = closing_brace(&mut chars);
+                    value += &unescape(&src[pending..i]);
+                    if !value.is_empty() {
+                        segments.push(StringSegment::Literal {
+                            value: std::mem::take(&mut value),
+                            span: self.offset(content, literal_start..i),
+                        });
+                    }
+                    let Some(close) = close else {
+                        let err = PaxParseError::new(
+                            ErrorKind::InvalidInterpolation,
+                            "unclosed \"{\" in string",
+                        )
+                        .annotation(self.offset(content, i..i + 1), "expression starts here")
+                        .help("close the expression with \"}\", or write \"{{\" to include a \"{\" in the string");
+                        self.errors.push(err);
+                        segments.push(StringSegment::Expression(Expression::Error(
+                            self.offset(content, i..src.len()),
+                        )));
+                        return (value, segments);
+                    };
+                    let expression = self.embedded_expression(self.offset(content, i + 1..close));
+                    segments.push(StringSegment::Expression(expression));
+                    pending = close + 1;
+                    literal_start = close + 1;
+                }
+                '}' => {
+                    let span = self.offset(content, i..i + 1);
+                    let err = PaxParseError::new(
+                        ErrorKind::InvalidInterpolation,
+                        "unmatched \"}\" in string",
+                    )
+                    .annotation(span, "no expression to close")
+                    .suggestion(
+                        span,
+                        "}}",
+                        "write \"}}\" to include a \"}\" in the string",
+                    );
+                    self.errors.push(err);
+                }
+                _ => (),
+            }
+        }
+        value += &unescape(&src[pending..]);
+        if !segments.is_empty() && !value.is_empty() {
+            segments.push(StringSegment::Literal {
+                value: std::mem::take(&mut value),
+                span: self.offset(content, literal_start..src.len()),
+            });
+        }
+        (value, segments)
+    }
+
+    fn embedded_expression(&mut self, span: Span) -> Expression {
+        let mut parser = self.embedded(span);
+        let expression = parser
+            .expression()
+            .and_then(|expression| match parser.is_at_eof() {
+                true => Ok(expression),
+                false => Err(parser.error([TokenKind::CloseCurlBrack])),
+            });
+        let expression = expression.unwrap_or_else(|e| {
+            parser.errors.push(e);
+            Expression::Error(span)
+        });
+        self.errors.append(&mut parser.errors);
+        self.errors
+            .append(&mut parser.tokens.inner_mut().take_errors());
+        expression
+    }
+
+    /// Span of a range relative to the start of `content`
+    fn offset(&self, content: Span, range: std::ops::Range<usize>) -> Span {
+        Span {
+            start: content.start + range.start,
+            end: content.start + range.end,
+        }
+    }
+}
+
+/// Finds the "}" closing an embedded expression, skipping nested braces as well as escapes
+/// and strings (delimited by \") inside the expression, such as in "{name == \"}\"}"
+fn closing_brace(chars: &mut Peekable<CharIndices>) -> Option<usize> {
+    let mut depth = 1;
+    let mut in_string = false;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars.next().is_some_and(|(_, c)| c == '"') => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
@@ -2,135 +2,250 @@ use pax_parser_macros::token_context;
 
 use crate::{
     ast::{
-        common::{Comment, Field, Handler, Identifier},
-        expression::Expression,
-        template::{Attribute, Binding, Conditional, Loop, MatchPattern, Tag, TemplateEntry},
+        common::{Comment, Field, Handler, Identifier, Receiver},
+        expression::{Expression, Value, Variable},
+        template::{
+            ArmPattern, Attribute, Binding, Conditional, ConditionalBranch, ElseBranch, LetBinding,
+            Loop, Match, MatchArm, MatchPattern, Slot, Tag, TemplateEntry, VariantPattern,
+        },
     },
-    lexer::TokenKind,
+    lexer::{Span, TokenKind},
 };
 
-use super::{Parser, PaxParseError};
+use super::{ErrorKind, Parser, PaxParseError};
 
 impl<'src> Parser<'src> {
     #[token_context("Template")]
-    pub fn template(&mut self) -> Result<Vec<TemplateEntry>, PaxParseError> {
+    pub fn template(&mut self) -> Vec<TemplateEntry> {
         let mut template: Vec<TemplateEntry> = vec![];
         loop {
+            let start = self.peek_span().start;
             let entry = match self.peek_token() {
                 TokenKind::CloseCurlBrack | TokenKind::AtSymbol | TokenKind::EOF => break,
                 TokenKind::OpenAngBrack => {
                     if self.peek_nth_token(1) == TokenKind::Slash {
                         break;
                     } else {
-                        TemplateEntry::Tag(self.tag()?)
+                        self.tag().map(TemplateEntry::Tag)
                     }
                 }
-                TokenKind::For => TemplateEntry::Loop(self.for_loop()?),
-                TokenKind::If => TemplateEntry::Conditional(self.condition()?),
-                TokenKind::Slot => TemplateEntry::Slot(self.slot()?),
-                TokenKind::Comment => {
-                    TemplateEntry::Comment(Comment(self.expect(TokenKind::Comment)?.span))
+                TokenKind::For => self.for_loop().map(TemplateEntry::Loop),
+                TokenKind::If => self.condition().map(TemplateEntry::Conditional),
+                TokenKind::Identifier if self.is_keyword_next(TokenKind::Slot) => {
+                    self.slot().map(TemplateEntry::Slot)
                 }
-                _ => {
-                    return Err(self.error([
-                        TokenKind::CloseCurlBrack,
-                        TokenKind::OpenAngBrack,
-                        TokenKind::For,
-                        TokenKind::If,
-                        TokenKind::Slot,
-                        TokenKind::Comment,
-                    ]))
+                TokenKind::Identifier if self.is_let_next() => {
+                    self.let_binding().map(TemplateEntry::Let)
                 }
+                TokenKind::Identifier if self.is_keyword_next(TokenKind::Match) => {
+                    self.match_entry().map(TemplateEntry::Match)
+                }
+                TokenKind::Comment => Ok(TemplateEntry::Comment(Comment(self.next_token().span))),
+                TokenKind::DocComment => {
+                    let count = self.doc_comment_count();
+                    if self.peek_nth_token(count) == TokenKind::OpenAngBrack
+                        && self.peek_nth_token(count + 1) == TokenKind::Identifier
+                    {
+                        self.tag().map(TemplateEntry::Tag)
+                    } else {
+                        // not documenting a tag, keep it as a regular comment
+                        Ok(TemplateEntry::Comment(Comment(self.next_token().span)))
+                    }
+                }
+                _ => Err(self.error([
+                    TokenKind::CloseCurlBrack,
+                    TokenKind::OpenAngBrack,
+                    TokenKind::For,
+                    TokenKind::If,
+                    TokenKind::Slot,
+                    TokenKind::Let,
+                    TokenKind::Match,
+                    TokenKind::Comment,
+                ])),
             };
-            template.push(entry);
+            template.push(entry.unwrap_or_else(|e| {
+                TemplateEntry::Error(self.recover(e, start, Self::is_template_boundary))
+            }));
         }
-        Ok(template)
+        template
     }
 
     #[token_context("Tag pair (<tag>..</tag>)")]
     fn tag(&mut self) -> Result<Tag, PaxParseError> {
-        self.expect(TokenKind::OpenAngBrack)?;
+        let docs = self.doc_comments();
+        let open = self.expect(TokenKind::OpenAngBrack)?;
+        let start = open.span.start;
         let name = self.expect(TokenKind::Identifier)?;
 
         let mut attributes = vec![];
         loop {
-            match self.peek_token() {
-                TokenKind::CloseAngBrack | TokenKind::Slash => break,
-                _ => {
-                    attributes.push(self.attribute()?);
+            if matches!(
+                self.peek_token(),
+                TokenKind::CloseAngBrack | TokenKind::Slash
+            ) || self.is_tag_start_next()
+                || self.is_at_eof()
+            {
+                break;
+            }
+            let start = self.peek_span().start;
+            match self.attribute() {
+                Ok(attribute) => attributes.push(attribute),
+                Err(e) => {
+                    self.recover(e, start, Self::is_attribute_boundary);
                 }
             }
         }
-        let body = match self.peek_token() {
+        let (body, open_tag, close_tag) = match self.peek_token() {
             TokenKind::CloseAngBrack => {
-                self.tokens.next();
-                let template = self.template()?;
-                let [_, _, ident, _] = self.expect_sequence([
+                self.next_token();
+                let open_tag = self.span_from(start);
+                self.open_tags.push(name.span);
+                let template = self.template();
+                self.open_tags.pop();
+                if let Some(err) = self.unclosed_tag_error(name.span, open_tag) {
+                    // leave the closing tag for the enclosing tag it belongs to
+                    self.errors.push(err);
+                    return Ok(Tag {
+                        docs,
+                        name: Identifier(name.span),
+                        attributes,
+                        body: template,
+                        span: self.span_from(start),
+                        open_tag,
+                        close_tag: None,
+                    });
+                }
+                let [close_start, _, ident, _] = self.expect_sequence([
                     TokenKind::OpenAngBrack,
                     TokenKind::Slash,
                     TokenKind::Identifier,
                     TokenKind::CloseAngBrack,
                 ])?;
                 if self.source_of(ident.span) != self.source_of(name.span) {
-                    return Err(PaxParseError::new("unexpected closing tag")
-                        .annotation(
-                            ident.span,
-                            format!(
-                                "found closing tag with name {:?}",
-                                self.source_of(ident.span)
-                            ),
-                        )
-                        .annotation(name.span, "expected to close this next"));
+                    // the tag itself is still well formed, keep it
+                    let name_src = self.source_of(name.span).to_owned();
+                    let err = PaxParseError::new(
+                        ErrorKind::MismatchedClosingTag,
+                        "unexpected closing tag",
+                    )
+                    .annotation(
+                        ident.span,
+                        format!(
+                            "found closing tag with name {:?}",
+                            self.source_of(ident.span)
+                        ),
+                    )
+                    .annotation(name.span, "expected to close this next")
+                    .suggestion(
+                        ident.span,
+                        &name_src,
+                        format!("rename the closing tag to </{}>", name_src),
+                    );
+                    self.errors.push(err);
                 }
-                template
+                let close_tag = self.span_from(close_start.span.start);
+                (template, open_tag, Some(close_tag))
             }
             TokenKind::Slash => {
-                self.tokens.next();
+                self.next_token();
                 self.expect(TokenKind::CloseAngBrack)?;
-                vec![]
+                (vec![], self.span_from(start), None)
+            }
+            _ => {
+                let span = self.peek_span();
+                return Err(
+                    self.expected_with_span(span, [TokenKind::CloseAngBrack, TokenKind::Slash])
+                );
             }
-            _ => unreachable!("should have continued trying to parse attributes?"),
         };
         Ok(Tag {
+            docs,
             name: Identifier(name.span),
             attributes,
             body,
+            span: self.span_from(start),
+            open_tag,
+            close_tag,
         })
     }
 
     #[token_context("Attribute (@handler=foo or key=value)")]
     fn attribute(&mut self) -> Result<Attribute, PaxParseError> {
+        let start = self.peek_span().start;
         Ok(match self.peek_token() {
             TokenKind::AtSymbol => {
-                let [_, key, _, value] = self.expect_sequence([
-                    TokenKind::AtSymbol,
-                    TokenKind::Identifier,
-                    TokenKind::Assign,
-                    TokenKind::Identifier,
-                ])?;
+                let [_, key] =
+                    self.expect_sequence([TokenKind::AtSymbol, TokenKind::Identifier])?;
+                if self.peek_token() == TokenKind::Colon {
+                    // settings syntax used in a tag, report it and continue as if it was "="
+                    let colon = self.next_token();
+                    let err = self
+                        .expected_with_span(colon.span, [TokenKind::Assign])
+                        .help("handlers are bound with \"=\" in tags, and with \":\" in @settings")
+                        .suggestion(colon.span, "=", "replace \":\" with \"=\"");
+                    self.errors.push(err);
+                } else {
+                    self.expect(TokenKind::Assign)?;
+                }
+                let receiver = self.receiver();
+                let value = self.expect(TokenKind::Identifier)?;
                 Attribute::Handler(Handler {
                     key: Identifier(key.span),
+                    receiver,
                     value: Identifier(value.span),
+                    span: self.span_from(start),
                 })
             }
-            TokenKind::Bind => {
-                let [_, _, key, _, value] = self.expect_sequence([
+            TokenKind::Identifier if self.is_binding_next() => {
+                let [_, _, key, _] = self.expect_sequence([
                     TokenKind::Bind,
                     TokenKind::Colon,
                     TokenKind::Identifier,
                     TokenKind::Assign,
-                    TokenKind::Identifier,
                 ])?;
+                let receiver = self.receiver();
+                let value = self.expect(TokenKind::Identifier)?;
                 Attribute::Binding(Binding {
                     key: Identifier(key.span),
+                    receiver,
                     value: Identifier(value.span),
+                    span: self.span_from(start),
                 })
             }
             TokenKind::Identifier => {
-                let [key, _] = self.expect_sequence([TokenKind::Identifier, TokenKind::Assign])?;
+                let key = self.expect(TokenKind::Identifier)?;
+                // a literal keyword followed by "=" is the next attribute, as in <Rect a true=1 />
+                let is_value_next = matches!(
+                    self.peek_token(),
+                    TokenKind::OpenCurlBrack
+                        | TokenKind::String
+                        | TokenKind::Integer
+                        | TokenKind::Float
+                        | TokenKind::Hashtag
+                ) || (self.is_literal_keyword_next()
+                    && self.peek_nth_token(1) != TokenKind::Assign);
+                if is_value_next {
+                    // report the missing "=" and continue parsing the value
+                    let span = self.peek_span();
+                    let insert_at = Span {
+                        start: key.span.end,
+                        end: key.span.end,
+                    };
+                    let err = self
+                        .expected_with_span(span, [TokenKind::Assign])
+                        .suggestion(insert_at, "=", "insert \"=\" between key and value");
+                    self.errors.push(err);
+                } else {
+                    self.expect(TokenKind::Assign)?;
+                }
+                let value_start = self.peek_span().start;
+                let value = self.literal_or_wrapped_expression().unwrap_or_else(|e| {
+                    Expression::Error(self.recover(e, value_start, Self::is_attribute_boundary))
+                });
                 Attribute::Field(Field {
                     key: Identifier(key.span),
-                    value: self.literal_or_wrapped_expression()?,
+                    value,
+                    span: self.span_from(start),
                 })
             }
             _ => {
@@ -145,7 +260,7 @@ impl<'src> Parser<'src> {
 
     #[token_context("For loop (for i in items {..})")]
     fn for_loop(&mut self) -> Result<Loop, PaxParseError> {
-        self.expect(TokenKind::For)?;
+        let start = self.expect(TokenKind::For)?.span.start;
 
         let pattern = if self.peek_token() == TokenKind::OpenParenth {
             let [_, t1, _, t2, _] = self.expect_sequence([
@@ -164,30 +279,230 @@ impl<'src> Parser<'src> {
         self.expect(TokenKind::In)?;
         let source = self.expression()?;
         self.expect(TokenKind::OpenCurlBrack)?;
-        let body = self.template()?;
+        let body = self.template();
         self.expect(TokenKind::CloseCurlBrack)?;
         Ok(Loop {
             pattern,
             source,
             body,
+            span: self.span_from(start),
         })
     }
 
-    #[token_context("Condition (if cond {..})")]
+    #[token_context("Condition (if cond {..} else {..})")]
     fn condition(&mut self) -> Result<Conditional, PaxParseError> {
-        self.expect(TokenKind::If)?;
+        let if_token = self.expect(TokenKind::If)?;
+        let start = if_token.span.start;
+        let mut branches = vec![self.conditional_branch(if_token.span)?];
+        let mut fallback = None;
+        while self.is_keyword_next(TokenKind::Else) {
+            let else_token = self.next_token();
+            if let Some(if_token) = self.next_token_if(|t| t == TokenKind::If) {
+                let keyword = else_token.span.join(if_token.span);
+                branches.push(self.conditional_branch(keyword)?);
+            } else {
+                let body = self.template_block()?;
+                fallback = Some(ElseBranch {
+                    keyword: else_token.span,
+                    body,
+                    span: self.span_from(else_token.span.start),
+                });
+                break;
+            }
+        }
+        Ok(Conditional {
+            branches,
+            fallback,
+            span: self.span_from(start),
+        })
+    }
+
+    /// The condition and body following an already consumed "if" or "else if"
+    fn conditional_branch(&mut self, keyword: Span) -> Result<ConditionalBranch, PaxParseError> {
         let condition = self.expression()?;
+        let body = self.template_block()?;
+        Ok(ConditionalBranch {
+            keyword,
+            condition,
+            body,
+            span: self.span_from(keyword.start),
+        })
+    }
+
+    fn template_block(&mut self) -> Result<Vec<TemplateEntry>, PaxParseError> {
         self.expect(TokenKind::OpenCurlBrack)?;
-        let body = self.template()?;
+        let body = self.template();
         self.expect(TokenKind::CloseCurlBrack)?;
-        Ok(Conditional { condition, body })
+        Ok(body)
+    }
+
+    #[token_context("Match (match value { Variant => {..} })")]
+    fn match_entry(&mut self) -> Result<Match, PaxParseError> {
+        let start = self.expect(TokenKind::Match)?.span.start;
+        let value = self.expression()?;
+        self.expect(TokenKind::OpenCurlBrack)?;
+        let mut arms = vec![];
+        while self
+            .next_token_if(|t| t == TokenKind::CloseCurlBrack)
+            .is_none()
+        {
+            let arm_start = self.peek_span().start;
+            let pattern = self.arm_pattern()?;
+            self.expect(TokenKind::FatArrow)?;
+            let body = self.template_block()?;
+            arms.push(MatchArm {
+                pattern,
+                body,
+                span: self.span_from(arm_start),
+            });
+            //skip commas between arms if they exist
+            self.next_token_if(|t| t == TokenKind::Comma);
+        }
+        Ok(Match {
+            value,
+            arms,
+            span: self.span_from(start),
+        })
+    }
+
+    #[token_context("Match pattern (Enum::Variant(a, b), Variant or _)")]
+    fn arm_pattern(&mut self) -> Result<ArmPattern, PaxParseError> {
+        let start = self.peek_span().start;
+        let (name, variant, arguments) = if self.peek_nth_token(1) == TokenKind::PathSep {
+            let enum_variant = self.enum_variant()?;
+            (
+                Some(enum_variant.name),
+                enum_variant.variant,
+                enum_variant.arguments,
+            )
+        } else {
+            let ident = self.expect(TokenKind::Identifier)?;
+            if self.source_of(ident.span) == "_" {
+                return Ok(ArmPattern::Wildcard(ident.span));
+            }
+            let arguments = if self.peek_token() == TokenKind::OpenParenth {
+                self.sequence_enclosed_in(TokenKind::OpenParenth, TokenKind::CloseParenth)?
+            } else {
+                vec![]
+            };
+            (None, Identifier(ident.span), arguments)
+        };
+        let bindings = arguments
+            .into_iter()
+            .filter_map(|argument| self.payload_binding(argument))
+            .collect();
+        Ok(ArmPattern::Variant(VariantPattern {
+            name,
+            variant,
+            bindings,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// The name a variant's payload is bound to. Patterns are parsed as
+    /// enum variants, so anything but a plain name is reported here.
+    fn payload_binding(&mut self, argument: Expression) -> Option<Identifier> {
+        let span = argument.span();
+        if let Expression::Value {
+            value:
+                Value::Variable(Variable {
+                    receiver: Receiver::Implicit,
+                    mut path,
+                    ..
+                }),
+            ..
+        } = argument
+        {
+            if path.len() == 1 {
+                return path.pop();
+            }
+        }
+        let err = PaxParseError::new(ErrorKind::InvalidPattern, "expected a name to bind to")
+            .annotation(span, "only names can bind a variant's payload");
+        self.errors.push(err);
+        None
     }
 
     #[token_context("Slot (slot(..))")]
-    fn slot(&mut self) -> Result<Expression, PaxParseError> {
-        self.expect_sequence([TokenKind::Slot, TokenKind::OpenParenth])?;
+    fn slot(&mut self) -> Result<Slot, PaxParseError> {
+        let [slot, _] = self.expect_sequence([TokenKind::Slot, TokenKind::OpenParenth])?;
         let source = self.expression()?;
         self.expect(TokenKind::CloseParenth)?;
-        Ok(source)
+        Ok(Slot {
+            source,
+            span: self.span_from(slot.span.start),
+        })
+    }
+
+    #[token_context("Let binding (let name = value;)")]
+    fn let_binding(&mut self) -> Result<LetBinding, PaxParseError> {
+        let [keyword, name, _] =
+            self.expect_sequence([TokenKind::Let, TokenKind::Identifier, TokenKind::Assign])?;
+        let value = self.expression()?;
+        self.expect(TokenKind::Semicolon)?;
+        Ok(LetBinding {
+            name: Identifier(name.span),
+            value,
+            span: self.span_from(keyword.span.start),
+        })
+    }
+
+    /// let name =, where let is only a keyword when followed by a name
+    pub fn is_let_next(&mut self) -> bool {
+        self.is_keyword_next(TokenKind::Let) && self.peek_nth_token(1) == TokenKind::Identifier
+    }
+
+    /// Checks if the next token starts the closing tag of an enclosing tag
+    /// instead of the one named `name`, in which case it was likely meant to be self-closing
+    fn unclosed_tag_error(&mut self, name: Span, open_tag: Span) -> Option<PaxParseError> {
+        if self.peek_token() != TokenKind::OpenAngBrack
+            || self.peek_nth_token(1) != TokenKind::Slash
+            || self.peek_nth_token(2) != TokenKind::Identifier
+        {
+            return None;
+        }
+        let closing = self.tokens.peek_nth(2)?.span;
+        let closing_src = self.source_of(closing);
+        if closing_src == self.source_of(name)
+            || !self
+                .open_tags
+                .iter()
+                .any(|&open| self.source_of(open) == closing_src)
+        {
+            return None;
+        }
+        let name_src = self.source_of(name).to_owned();
+        let insert_at = Span {
+            start: open_tag.end - 1,
+            end: open_tag.end - 1,
+        };
+        Some(
+            PaxParseError::new(ErrorKind::MismatchedClosingTag, "unclosed tag")
+                .annotation(name, "this tag is never closed")
+                .annotation(
+                    closing,
+                    format!("found closing tag of enclosing tag {:?}", closing_src),
+                )
+                .help(format!(
+                    "add a closing </{}> tag, or make the tag self-closing",
+                    name_src
+                ))
+                .suggestion(insert_at, "/", format!("make <{}> self-closing", name_src)),
+        )
+    }
+
+    fn is_attribute_boundary(&mut self) -> bool {
+        matches!(
+            self.peek_token(),
+            TokenKind::CloseAngBrack | TokenKind::Slash | TokenKind::AtSymbol
+        ) || (self.peek_token() == TokenKind::Identifier
+            && self.peek_nth_token(1) == TokenKind::Assign)
+            || self.is_binding_next()
+            || self.is_tag_start_next()
+    }
+
+    /// bind:key=value, where bind is only a keyword when followed by ":"
+    fn is_binding_next(&mut self) -> bool {
+        self.is_keyword_next(TokenKind::Bind) && self.peek_nth_token(1) == TokenKind::Colon
     }
 }
@@ -1,28 +1,48 @@
+use std::num::IntErrorKind;
+
 use pax_parser_macros::token_context;
 
 use crate::{
     ast::{
-        common::Identifier,
-        expression::{EnumVariant, Expression, FunctionCall, Value},
+        common::{Identifier, Receiver},
+        expression::{
+            BoolLiteral, ColorLiteral, EnumVariant, Expression, FloatLiteral, FunctionCall,
+            IntLiteral, Value, Variable,
+        },
     },
-    lexer::TokenKind,
+    lexer::{Span, TokenKind},
 };
 
-use super::{Parser, PaxParseError};
+use super::{ErrorKind, Parser, PaxParseError};
 
 impl<'src> Parser<'src> {
     #[token_context("Value (5px, {..})")]
     pub fn value(&mut self) -> Result<Value, PaxParseError> {
         Ok(match self.peek_token() {
-            TokenKind::Integer => Value::Int(self.next_token().span),
-            TokenKind::Float => Value::Float(self.next_token().span),
+            TokenKind::Integer => Value::Int(self.int()),
+            TokenKind::Float => Value::Float(self.float()),
+            TokenKind::Hashtag => Value::Color(self.color()?),
+            TokenKind::Identifier if self.is_literal_keyword_next() => {
+                let token = self.next_token();
+                match self.source_of(token.span) {
+                    "true" | "false" => Value::Bool(BoolLiteral {
+                        value: self.source_of(token.span) == "true",
+                        span: token.span,
+                    }),
+                    _ => Value::None(token.span),
+                }
+            }
+            TokenKind::Identifier if self.is_receiver_next() => match self.peek_nth_token(3) {
+                TokenKind::OpenParenth => Value::FunctionCall(self.function_call()?),
+                _ => Value::Variable(self.variable()?),
+            },
             TokenKind::Identifier => match self.peek_nth_token(1) {
                 TokenKind::OpenParenth => Value::FunctionCall(self.function_call()?),
                 TokenKind::PathSep => Value::EnumVariant(self.enum_variant()?),
                 TokenKind::OpenCurlBrack if self.is_map_next() => Value::Object(self.object()?),
                 _ => Value::Variable(self.variable()?),
             },
-            TokenKind::String => Value::String(self.next_token().span),
+            TokenKind::String => self.string(),
             TokenKind::OpenCurlBrack => Value::Object(self.object()?),
             TokenKind::OpenSquareBrack => Value::List(
                 self.sequence_enclosed_in(TokenKind::OpenSquareBrack, TokenKind::CloseSquareBrack)?,
@@ -34,6 +54,10 @@ impl<'src> Parser<'src> {
                 return Err(self.error([
                     TokenKind::Integer,
                     TokenKind::Float,
+                    TokenKind::Hashtag,
+                    TokenKind::True,
+                    TokenKind::False,
+                    TokenKind::None,
                     TokenKind::Identifier,
                     TokenKind::String,
                     TokenKind::OpenCurlBrack,
@@ -44,20 +68,173 @@ impl<'src> Parser<'src> {
         })
     }
 
+    /// true, false, none and null are only literals where a value is expected,
+    /// and can be used as names elsewhere (<Rect none=5 />)
+    pub fn is_literal_keyword_next(&mut self) -> bool {
+        let span = self.peek_span();
+        self.is_keyword_next(TokenKind::True)
+            || self.is_keyword_next(TokenKind::False)
+            || self.is_keyword_next(TokenKind::None)
+            || (self.peek_token() == TokenKind::Identifier && self.source_of(span) == "null")
+    }
+
+    fn int(&mut self) -> IntLiteral {
+        let span = self.next_token().span;
+        let (digits, radix) = int_digits(self.source_of(span));
+        let value = match i64::from_str_radix(&digits, radix) {
+            Ok(value) => value,
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                let err = PaxParseError::new(ErrorKind::NumberOutOfRange, "integer out of range")
+                    .annotation(span, "doesn't fit in a 64 bit integer")
+                    .help(format!("the largest integer allowed is {}", i64::MAX));
+                self.errors.push(err);
+                i64::MAX
+            }
+            // malformed numbers are reported by the lexer
+            Err(_) => 0,
+        };
+        IntLiteral { value, span }
+    }
+
+    /// Parses -9223372036854775808 (i64::MIN) as a single literal, since its magnitude
+    /// doesn't fit in an i64 on its own. Other negative numbers are parsed as a negation.
+    pub fn min_int(&mut self) -> Option<IntLiteral> {
+        let minus = self.peek_span();
+        let int = self
+            .tokens
+            .peek_nth(1)
+            .filter(|t| t.kind == TokenKind::Integer)?
+            .span;
+        let (digits, radix) = int_digits(self.source_of(int));
+        if u64::from_str_radix(&digits, radix) != Ok(i64::MIN.unsigned_abs()) {
+            return None;
+        }
+        self.next_token();
+        self.next_token();
+        Some(IntLiteral {
+            value: i64::MIN,
+            span: Span {
+                start: minus.start,
+                end: int.end,
+            },
+        })
+    }
+
+    fn float(&mut self) -> FloatLiteral {
+        let span = self.next_token().span;
+        let value: f64 = self
+            .source_of(span)
+            .replace('_', "")
+            .parse()
+            .unwrap_or_default();
+        if value.is_infinite() {
+            let err = PaxParseError::new(ErrorKind::NumberOutOfRange, "float out of range")
+                .annotation(span, "doesn't fit in a 64 bit float")
+                .help(format!("the largest float allowed is {:e}", f64::MAX));
+            self.errors.push(err);
+        }
+        FloatLiteral { value, span }
+    }
+
+    #[token_context("Color (#rrggbb)")]
+    fn color(&mut self) -> Result<ColorLiteral, PaxParseError> {
+        let hashtag = self.expect(TokenKind::Hashtag)?;
+        // the digits are lexed as a single token (an identifier, or an integer if they start
+        // with a digit) directly following the "#", with no whitespace in between
+        let next = self.peek_span();
+        let is_digits = self
+            .source_of(next)
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric());
+        if next.start == self.prev_token_end && !self.is_at_eof() && is_digits {
+            self.next_token();
+        }
+        let span = self.span_from(hashtag.span.start);
+        let digits_start = hashtag.span.end;
+        let digits = self.source_of(Span {
+            start: digits_start,
+            end: span.end,
+        });
+        if let Some(i) = digits.find(|c: char| !c.is_ascii_hexdigit()) {
+            let digit = Span {
+                start: digits_start + i,
+                end: digits_start + i + 1,
+            };
+            return Err(
+                PaxParseError::new(ErrorKind::InvalidColor, "invalid color literal")
+                    .annotation(digit, "not a hex digit")
+                    .annotation(span, "in this color"),
+            );
+        }
+        let channels: Vec<u8> = match digits.len() {
+            // #rgb is short for #rrggbb
+            3 => digits
+                .chars()
+                .map(|c| c.to_digit(16).unwrap() as u8 * 0x11)
+                .collect(),
+            6 | 8 => (0..digits.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+                .collect(),
+            n => {
+                let err = PaxParseError::new(ErrorKind::InvalidColor, "invalid color literal")
+                    .annotation(span, format!("expected 3, 6 or 8 hex digits, found {}", n))
+                    .help("colors are written as #rgb, #rrggbb or #rrggbbaa");
+                return Err(err);
+            }
+        };
+        Ok(ColorLiteral {
+            r: channels[0],
+            g: channels[1],
+            b: channels[2],
+            a: channels.get(3).copied().unwrap_or(255),
+            span,
+        })
+    }
+
     #[token_context("Variable")]
-    fn variable(&mut self) -> Result<Vec<Identifier>, PaxParseError> {
+    fn variable(&mut self) -> Result<Variable, PaxParseError> {
+        let start = self.peek_span().start;
+        let receiver = self.receiver();
         let mut var_path = Vec::new();
         loop {
             let ident = self.expect(TokenKind::Identifier)?;
             var_path.push(Identifier(ident.span));
-            if self.next_token_if(|t| t == TokenKind::Period).is_none() {
+            // leave .method(..) to be parsed as a method call on the path so far
+            let is_path_next = self.peek_token() == TokenKind::Period
+                && self.peek_nth_token(1) == TokenKind::Identifier
+                && self.peek_nth_token(2) != TokenKind::OpenParenth;
+            if !is_path_next {
                 break;
             }
+            self.next_token();
         }
-        Ok(var_path)
+        Ok(Variable {
+            receiver,
+            path: var_path,
+            span: self.span_from(start),
+        })
     }
 
-    fn enum_variant(&mut self) -> Result<EnumVariant, PaxParseError> {
+    /// Consumes self. or this. if present
+    pub fn receiver(&mut self) -> Receiver {
+        if !self.is_receiver_next() {
+            return Receiver::Implicit;
+        }
+        let receiver = self.next_token();
+        self.next_token();
+        Receiver::SelfRef(receiver.span)
+    }
+
+    fn is_receiver_next(&mut self) -> bool {
+        let span = self.peek_span();
+        self.peek_token() == TokenKind::Identifier
+            && matches!(self.source_of(span), "self" | "this")
+            && self.peek_nth_token(1) == TokenKind::Period
+    }
+
+    pub fn enum_variant(&mut self) -> Result<EnumVariant, PaxParseError> {
+        let start = self.peek_span().start;
         let [name, _, variant] = self.expect_sequence([
             TokenKind::Identifier,
             TokenKind::PathSep,
@@ -72,21 +249,27 @@ impl<'src> Parser<'src> {
             name: Identifier(name.span),
             variant: Identifier(variant.span),
             arguments: args,
+            span: self.span_from(start),
         })
     }
 
     #[token_context("Function call")]
     fn function_call(&mut self) -> Result<FunctionCall, PaxParseError> {
+        let start = self.peek_span().start;
+        let receiver = self.receiver();
         let ident = self.expect(TokenKind::Identifier)?;
+        let arguments =
+            self.sequence_enclosed_in(TokenKind::OpenParenth, TokenKind::CloseParenth)?;
         Ok(FunctionCall {
+            receiver,
             name: Identifier(ident.span),
-            arguments: self
-                .sequence_enclosed_in(TokenKind::OpenParenth, TokenKind::CloseParenth)?,
+            arguments,
+            span: self.span_from(start),
         })
     }
 
     #[token_context("Sequence ([foo, 5px], or (foo, 5px))")]
-    fn sequence_enclosed_in(
+    pub fn sequence_enclosed_in(
         &mut self,
         open: TokenKind,
         close: TokenKind,
@@ -103,3 +286,13 @@ impl<'src> Parser<'src> {
         Ok(entries)
     }
 }
+
+/// The digits of an integer literal without underscores, and their radix
+fn int_digits(text: &str) -> (String, u32) {
+    let text = text.replace('_', "");
+    match text.get(..2) {
+        Some("0x" | "0X") => (text[2..].to_owned(), 16),
+        Some("0b" | "0B") => (text[2..].to_owned(), 2),
+        _ => (text, 10),
+    }
+}
@@ -0,0 +1,244 @@
+use std::ops::Range;
+
+use codespan_reporting::files::{Error, Files};
+
+use crate::{ast::PaxAst, lexer::Span, Parser, PaxParseError};
+
+/// Identifies a file added to a [`SourceMap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+/// Zero-based position in a file, with the column counted in
+/// characters, UTF-8 bytes and UTF-16 code units (as used by LSP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+    pub utf8_column: usize,
+    pub utf16_column: usize,
+}
+
+struct SourceFile {
+    name: String,
+    source: String,
+    /// byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+/// Holds the sources of many pax files, so that spans (which are byte offsets
+/// into a single file) can be resolved to file names and lines/columns.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        let source = source.into();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.files.push(SourceFile {
+            name: name.into(),
+            source,
+            line_starts,
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn file_ids(&self) -> impl Iterator<Item = FileId> {
+        (0..self.files.len()).map(FileId)
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.files[file.0].name
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        &self.files[file.0].source
+    }
+
+    /// Parses a file, recovering from errors. The errors
+    /// returned have their annotations assigned to this file.
+    pub fn parse(&self, file: FileId) -> (PaxAst, Vec<PaxParseError>) {
+        let (ast, errors) = Parser::new(self.source(file)).pax_recovering();
+        let errors = errors.into_iter().map(|e| e.in_file(file)).collect();
+        (ast, errors)
+    }
+
+    /// Position of a byte offset, offsets past the end are clamped to the end of the file,
+    /// and offsets inside a multibyte character to the start of the character
+    pub fn line_column(&self, file: FileId, offset: usize) -> LineColumn {
+        let file = &self.files[file.0];
+        let mut offset = offset.min(file.source.len());
+        while !file.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = file.line_starts.partition_point(|&start| start <= offset) - 1;
+        let before = &file.source[file.line_starts[line]..offset];
+        LineColumn {
+            line,
+            column: before.chars().count(),
+            utf8_column: before.len(),
+            utf16_column: before.encode_utf16().count(),
+        }
+    }
+
+    /// Start and end position of a span
+    pub fn span_location(&self, file: FileId, span: Span) -> (LineColumn, LineColumn) {
+        (
+            self.line_column(file, span.start),
+            self.line_column(file, span.end),
+        )
+    }
+
+    fn get(&self, file: FileId) -> Result<&SourceFile, Error> {
+        self.files.get(file.0).ok_or(Error::FileMissing)
+    }
+}
+
+impl<'a> Files<'a> for SourceMap {
+    type FileId = FileId;
+    type Name = &'a str;
+    type Source = &'a str;
+
+    fn name(&'a self, id: FileId) -> Result<&'a str, Error> {
+        Ok(&self.get(id)?.name)
+    }
+
+    fn source(&'a self, id: FileId) -> Result<&'a str, Error> {
+        Ok(&self.get(id)?.source)
+    }
+
+    fn line_index(&'a self, id: FileId, byte_index: usize) -> Result<usize, Error> {
+        let file = self.get(id)?;
+        Ok(file
+            .line_starts
+            .partition_point(|&start| start <= byte_index)
+            - 1)
+    }
+
+    fn line_range(&'a self, id: FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        let file = self.get(id)?;
+        let max = file.line_starts.len() - 1;
+        let start = *file
+            .line_starts
+            .get(line_index)
+            .ok_or(Error::LineTooLarge {
+                given: line_index,
+                max,
+            })?;
+        let end = file
+            .line_starts
+            .get(line_index + 1)
+            .copied()
+            .unwrap_or(file.source.len());
+        Ok(start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineColumn, SourceMap};
+    use crate::{to_sarif_with_source_map, ColorMode, ErrorKind, PaxParseError, Span};
+
+    #[test]
+    fn test_line_column() {
+        let mut map = SourceMap::new();
+        let _ = map.add("a.pax", "<Group/>");
+        let file = map.add("b.pax", "<Text\n  text=\"ö𝄞\" x=5 />");
+        let offset = map.source(file).find("x=").unwrap();
+        assert_eq!(
+            map.line_column(file, offset),
+            LineColumn {
+                line: 1,
+                column: 12,
+                utf8_column: 16,
+                utf16_column: 13,
+            }
+        );
+        assert_eq!(map.line_column(file, 0).line, 0);
+        assert_eq!(map.line_column(file, 1000).column, 18);
+    }
+
+    #[test]
+    fn test_offsets_inside_multibyte_characters() {
+        let mut map = SourceMap::new();
+        let file = map.add("a.pax", "<Rect a=1 /> <Gé");
+        let len = map.source(file).len();
+        let position = map.line_column(file, len - 1);
+        assert_eq!((position.column, position.utf8_column), (15, 15));
+        assert_eq!(map.line_column(file, len).column, 16);
+
+        let err = PaxParseError::new(ErrorKind::UnexpectedToken, "unexpected end of file")
+            .annotation_in_file(
+                file,
+                Span {
+                    start: len - 1,
+                    end: len,
+                },
+                "here",
+            );
+        assert!(err.to_json_with_source_map(&map).is_ok());
+        assert!(to_sarif_with_source_map(&[err], &map).is_ok());
+    }
+
+    #[test]
+    fn test_errors_in_several_files() {
+        let mut map = SourceMap::new();
+        let good = map.add("good.pax", "<Group/>");
+        let bad = map.add("bad.pax", "<Group></Grup>");
+        assert!(map.parse(good).1.is_empty());
+        let (_, errors) = map.parse(bad);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file(), Some(bad));
+    }
+
+    #[test]
+    fn test_render_annotations_in_several_files() {
+        let mut map = SourceMap::new();
+        let component = map.add("component.pax", "<Group/>");
+        let usage = map.add("usage.pax", "<Component\n  x=5 />");
+        let err = PaxParseError::new(ErrorKind::UnexpectedToken, "unknown property")
+            .annotation(Span { start: 13, end: 14 }, "not a property of Component")
+            .annotation_in_file(component, Span { start: 0, end: 8 }, "defined here")
+            .in_file(usage);
+
+        let rendered = err.render_with_source_map(&map, ColorMode::Never).unwrap();
+        assert!(rendered.contains("usage.pax:2:3"));
+        assert!(rendered.contains("component.pax:1:1"));
+
+        let unassigned = PaxParseError::new(ErrorKind::UnexpectedToken, "no file")
+            .annotation(Span { start: 0, end: 1 }, "here");
+        assert!(unassigned
+            .render_with_source_map(&map, ColorMode::Never)
+            .is_err());
+    }
+
+    #[test]
+    fn test_serialize_suggestions_in_several_files() {
+        let mut map = SourceMap::new();
+        let component = map.add("component.pax", "<Group/>");
+        let usage = map.add("usage.pax", "<Component x=5 />");
+        let span = Span { start: 11, end: 12 };
+        let unassigned = PaxParseError::new(ErrorKind::UnexpectedToken, "unknown property")
+            .annotation_in_file(usage, span, "not a property of Component")
+            .suggestion(span, "y", "rename to y");
+        assert!(unassigned.to_json_with_source_map(&map).is_err());
+        assert!(to_sarif_with_source_map(&[unassigned], &map).is_err());
+
+        let err = PaxParseError::new(ErrorKind::UnexpectedToken, "unknown property")
+            .annotation_in_file(usage, span, "not a property of Component")
+            .suggestion_in_file(component, Span { start: 1, end: 6 }, "Component", "rename");
+        let json = err.to_json_with_source_map(&map).unwrap();
+        assert!(json.contains("\"file\":\"usage.pax\""));
+        assert!(json.contains("\"file\":\"component.pax\""));
+        assert!(to_sarif_with_source_map(&[err], &map)
+            .unwrap()
+            .contains("component.pax"));
+    }
+}
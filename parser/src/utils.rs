@@ -39,6 +39,10 @@ impl<V, I: Iterator<Item = V>> MultiPeek<I> {
     pub fn inner(&self) -> &I {
         &self.itr
     }
+
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.itr
+    }
 }
 
 impl<V, I: Iterator<Item = V>> Iterator for MultiPeek<I> {